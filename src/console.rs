@@ -2,8 +2,8 @@ use clap::{Parser, Subcommand};
 use qualia::{Store, Q};
 use regex::Regex;
 use rustyline::Editor;
-use shell_words;
 use std::borrow::Cow;
+use std::io::{BufRead, IsTerminal};
 
 use crate::{AHResult, CommonOpts, SubCmd};
 
@@ -19,6 +19,9 @@ enum ConsoleSubCommand {
     #[clap(flatten)]
     Base(SubCmd),
 
+    #[clap(about = "Clear the screen")]
+    Clear,
+
     #[clap(about = "Quit the console")]
     Quit,
 }
@@ -39,7 +42,7 @@ struct InputWord {
 fn quote(input: &str, existing_delimiters: String) -> String {
     if existing_delimiters == "\"" {
         "\"".to_string() + &input.replace("\\", "\\\\").replace("\"", "\\\"") + "\""
-    } else if input.find(|c| c == ' ' || c == '"' || c == '\\').is_some() {
+    } else if input.find([' ', '"', '\\']).is_some() {
         input
             .replace("\\", "\\\\")
             .replace("\"", "\\\"")
@@ -64,9 +67,7 @@ fn unquote(input: &str) -> String {
         };
     }
 
-    loop {
-        let next = read_or!(chars, break);
-
+    while let Some(next) = chars.next() {
         match next {
             '\\' => {
                 let next = read_or!(chars, break);
@@ -96,7 +97,7 @@ fn words_up_to_cursor_pos(input: impl AsRef<str>, pos: usize) -> Vec<InputWord>
             let original = &input[range];
             let word = unquote(original);
 
-            if word != "" || word_pos == pos {
+            if !word.is_empty() || word_pos == pos {
                 Some(InputWord {
                     pos: word_pos,
                     word,
@@ -108,7 +109,7 @@ fn words_up_to_cursor_pos(input: impl AsRef<str>, pos: usize) -> Vec<InputWord>
         })
         .collect();
 
-    if result.len() == 0 || pos > farthest_parsed {
+    if result.is_empty() || pos > farthest_parsed {
         result.extend(vec![InputWord {
             pos,
             word: "".to_string(),
@@ -150,11 +151,11 @@ impl<'store> ConsoleHelper<'store> {
             "name_pattern" => self
                 .store
                 .query(Q.equal("type", "item"))
-                .iter_converted::<crate::types::Item>(&self.store)
+                .iter_converted::<crate::types::Item>(self.store)
                 .unwrap()
                 .map(|item| item.name)
                 .collect(),
-            "location" => self
+            "location" | "source" | "destination" => self
                 .store
                 .query(Q.equal("type", "location"))
                 .iter_as::<crate::types::Location>()
@@ -165,8 +166,8 @@ impl<'store> ConsoleHelper<'store> {
         }
     }
 
-    fn completion_candidates(&self, words: &Vec<InputWord>) -> Vec<String> {
-        let mut words = words.clone();
+    fn completion_candidates(&self, words: &[InputWord]) -> Vec<String> {
+        let mut words = words.to_vec();
         let mut app = &<ConsoleOpts as clap::CommandFactory>::command();
 
         while words.len() > 1 {
@@ -229,7 +230,7 @@ impl rustyline::hint::Hinter for ConsoleHelper<'_> {
         let last_word = &words[words.len() - 1];
 
         // Don't hint at the start of a word.
-        if last_word.word == "" {
+        if last_word.word.is_empty() {
             return None;
         }
 
@@ -245,47 +246,79 @@ impl rustyline::hint::Hinter for ConsoleHelper<'_> {
 
 impl rustyline::validate::Validator for ConsoleHelper<'_> {}
 
-pub(crate) fn run_console(opts: CommonOpts) -> AHResult<()> {
-    let store = opts.open_store().unwrap();
+/// Runs a single line of console input, returning whether the console should keep going.
+fn run_console_line(line: &str) -> bool {
+    || -> AHResult<bool> {
+        let words = shell_words::split(line)?;
 
-    let mut rl = Editor::<ConsoleHelper>::new()?;
-    rl.set_helper(Some(ConsoleHelper { store: &store }));
+        if words.is_empty() {
+            return Ok(true);
+        }
 
-    while let Ok(line) = rl.readline("pachinko> ") {
-        let continue_console = || -> AHResult<bool> {
-            let words = shell_words::split(&line)?;
+        if words[0] == "help" {
+            <ConsoleOpts as clap::CommandFactory>::command()
+                .help_template("Available commands:\n{subcommands}")
+                .print_help()?;
 
-            if words.len() == 0 {
-                return Ok(true);
-            }
+            return Ok(true);
+        }
 
-            if words[0] == "help" {
-                <ConsoleOpts as clap::CommandFactory>::command()
-                    .help_template("Available commands:\n{subcommands}")
-                    .print_help()?;
+        let console_opts = ConsoleOpts::try_parse_from(words)?;
 
-                return Ok(true);
+        match console_opts.subcmd {
+            ConsoleSubCommand::Clear => {
+                crossterm::execute!(
+                    std::io::stdout(),
+                    crossterm::terminal::Clear(crossterm::terminal::ClearType::All),
+                    crossterm::cursor::MoveTo(0, 0),
+                )?;
+
+                Ok(true)
             }
+            ConsoleSubCommand::Quit => Ok(false),
+            ConsoleSubCommand::Base(SubCmd::Console(_)) => Ok(true),
+            ConsoleSubCommand::Base(sc) => sc.invoke().map(|_| true),
+        }
+    }()
+    .unwrap_or_else(|e| {
+        println!("Error: {}", e);
 
-            let console_opts = ConsoleOpts::try_parse_from(words)?;
+        true
+    })
+}
 
-            match console_opts.subcmd {
-                ConsoleSubCommand::Quit => Ok(false),
-                ConsoleSubCommand::Base(SubCmd::Console(_)) => Ok(true),
-                ConsoleSubCommand::Base(sc) => sc.invoke().map(|_| true),
+pub(crate) fn run_console(opts: CommonOpts) -> AHResult<()> {
+    // Commands piped in are run as a script, without prompting or keeping history.
+    if !std::io::stdin().is_terminal() {
+        for line in std::io::stdin().lock().lines() {
+            if !run_console_line(&line?) {
+                break;
             }
-        }()
-        .unwrap_or_else(|e| {
-            println!("Error: {}", e);
+        }
+
+        return Ok(());
+    }
+
+    let store = opts.open_store().unwrap();
+    let history_path = opts.console_history_path()?;
 
-            true
-        });
+    let mut rl = Editor::<ConsoleHelper>::new()?;
+    rl.set_helper(Some(ConsoleHelper { store: &store }));
+    // There is no history yet the first time the console is run.
+    let _ = rl.load_history(&history_path);
+
+    while let Ok(line) = rl.readline("pachinko> ") {
+        if !line.trim().is_empty() {
+            rl.add_history_entry(line.as_str());
+        }
 
-        if !continue_console {
+        if !run_console_line(&line) {
             break;
         }
     }
 
+    rl.save_history(&history_path)?;
+
     Ok(())
 }
 
@@ -464,7 +497,7 @@ mod tests {
 
     fn open_test_store() -> (TempDir, Store) {
         let temp_dir = Builder::new().prefix("pachinko-cli").tempdir().unwrap();
-        let store_path = temp_dir.path().clone().join("pachinko-test-store.qualia");
+        let store_path = temp_dir.path().join("pachinko-test-store.qualia");
 
         (temp_dir, Store::open(store_path).unwrap())
     }
@@ -475,24 +508,44 @@ mod tests {
         let helper = &ConsoleHelper { store: &store };
 
         assert_eq!(
-            helper.completion_candidates(&vec![word!(0, "")]),
+            helper.completion_candidates(&[word!(0, "")]),
             vec![
                 "add".to_string(),
                 "add-location".to_string(),
+                "clear".to_string(),
+                "compact".to_string(),
+                "config".to_string(),
                 "console".to_string(),
                 "delete".to_string(),
+                "doctor".to_string(),
                 "dump".to_string(),
                 "editor".to_string(),
+                "export".to_string(),
+                "find".to_string(),
+                "history".to_string(),
+                "import".to_string(),
                 "items".to_string(),
+                "labels".to_string(),
+                "last".to_string(),
                 "locations".to_string(),
+                "merge-locations".to_string(),
+                "move".to_string(),
                 "quickadd".to_string(),
                 "quit".to_string(),
+                "rebalance".to_string(),
+                "redo".to_string(),
+                "rename-location".to_string(),
+                "restore".to_string(),
+                "stats".to_string(),
+                "swap-bins".to_string(),
+                "tag".to_string(),
                 "undo".to_string(),
+                "untag".to_string(),
             ],
         );
 
         assert_eq!(
-            helper.completion_candidates(&vec![word!(0, "q")]),
+            helper.completion_candidates(&[word!(0, "q")]),
             vec!["quickadd".to_string(), "quit".to_string(),],
         );
     }
@@ -503,7 +556,7 @@ mod tests {
         let helper = &ConsoleHelper { store: &store };
 
         assert_eq!(
-            helper.completion_candidates(&vec![word!(0, "add-location"), word!(13, "")]),
+            helper.completion_candidates(&[word!(0, "add-location"), word!(13, "")]),
             Vec::<String>::new(),
         );
     }
@@ -517,6 +570,7 @@ mod tests {
             object_id: None,
             name: "location".to_string(),
             num_bins: 1,
+            rest: Object::new(),
         };
         checkpoint.add_with_id(&mut location).unwrap();
         checkpoint
@@ -551,7 +605,7 @@ mod tests {
         let helper = &ConsoleHelper { store: &store };
 
         assert_eq!(
-            helper.completion_candidates(&vec![word!(0, "delete"), word!(7, "a")]),
+            helper.completion_candidates(&[word!(0, "delete"), word!(7, "a")]),
             vec!["Abc".to_string(), "aaa".to_string()],
         );
     }
@@ -575,12 +629,27 @@ mod tests {
                 "num_bins" => 1,
             ))
             .unwrap();
+        checkpoint
+            .add(object!(
+                "type" => "location",
+                "name" => "Attic",
+                "num_bins" => 1,
+            ))
+            .unwrap();
         checkpoint.commit("").unwrap();
 
         let helper = &ConsoleHelper { store: &store };
 
         assert_eq!(
-            helper.completion_candidates(&vec![word!(0, "add"), word!(4, "l")]),
+            helper.completion_candidates(&[word!(0, "add"), word!(4, "l")]),
+            vec!["Loc2".to_string(), "loc1".to_string()],
+        );
+        assert_eq!(
+            helper.completion_candidates(&[word!(0, "quickadd"), word!(9, "l")]),
+            vec!["Loc2".to_string(), "loc1".to_string()],
+        );
+        assert_eq!(
+            helper.completion_candidates(&[word!(0, "move"), word!(5, "item"), word!(10, "l")]),
             vec!["Loc2".to_string(), "loc1".to_string()],
         );
     }
@@ -593,6 +662,7 @@ mod tests {
             object_id: None,
             name: "location".to_string(),
             num_bins: 1,
+            rest: Object::new(),
         };
         checkpoint.add_with_id(&mut location).unwrap();
         checkpoint
@@ -0,0 +1,120 @@
+use anyhow::bail;
+
+use crate::AHResult;
+
+/// Joins fields into a CSV line, quoting any that contain commas, quotes or newlines.
+pub fn format_record<S: AsRef<str>>(fields: &[S]) -> String {
+    fields
+        .iter()
+        .map(|field| {
+            let field = field.as_ref();
+
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Splits CSV text into records, along with the line each starts on. Newlines inside quoted fields
+/// stay part of their record.
+pub fn split_records(contents: &str) -> Vec<(usize, &str)> {
+    let mut records = vec![];
+    let mut start = 0;
+    let mut start_line = 1;
+    let mut line = 1;
+    let mut in_quotes = false;
+
+    for (i, c) in contents.char_indices() {
+        match c {
+            // Escaped quotes come in pairs, so they leave this as it was.
+            '"' => in_quotes = !in_quotes,
+            '\n' => {
+                line += 1;
+
+                if !in_quotes {
+                    let record = &contents[start..i];
+                    records.push((start_line, record.strip_suffix('\r').unwrap_or(record)));
+                    start = i + 1;
+                    start_line = line;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if start < contents.len() {
+        records.push((start_line, &contents[start..]));
+    }
+
+    records
+}
+
+/// Splits a CSV line into fields, undoing the quoting done by `format_record`.
+pub fn parse_record(line: &str) -> AHResult<Vec<String>> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => in_quotes = false,
+            ('"', false) if field.is_empty() => in_quotes = true,
+            (',', false) => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        bail!("unterminated quoted field");
+    }
+
+    fields.push(field);
+
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_record_should_quote_only_when_needed() {
+        assert_eq!(
+            format_record(&["Garage", "2", "Nuts, bolts", "say \"hi\""]),
+            "Garage,2,\"Nuts, bolts\",\"say \"\"hi\"\"\""
+        );
+    }
+
+    #[test]
+    fn parse_record_should_undo_format_record() {
+        let fields = vec!["Garage", "", "Nuts, bolts", "say \"hi\""];
+
+        assert_eq!(parse_record(&format_record(&fields)).unwrap(), fields);
+    }
+
+    #[test]
+    fn split_records_should_keep_quoted_newlines() {
+        assert_eq!(
+            split_records("location,bin,name,size\r\nGarage,,\"Two\nlines\",S\nShed,1,Rake,M"),
+            vec![
+                (1, "location,bin,name,size"),
+                (2, "Garage,,\"Two\nlines\",S"),
+                (4, "Shed,1,Rake,M"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_record_should_fail_on_unterminated_quotes() {
+        assert!(parse_record("Garage,\"Nuts").is_err());
+    }
+}
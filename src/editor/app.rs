@@ -1,43 +1,82 @@
 use std::{
+    path::Path,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
-    time::{Instant},
+    time::{Duration, Instant},
     vec,
 };
 
-use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers, ModifierKeyCode};
+use anyhow::{anyhow, bail, Context};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, ModifierKeyCode};
 
 use lazy_static::lazy_static;
 use qualia::Store;
+use serde_json::json;
 use tui::{
     backend::Backend,
-    layout::{Constraint, Margin, Rect},
+    layout::{Alignment, Constraint, Margin, Rect},
     style::{Color, Modifier, Style},
     text::Span,
-    widgets::{Block, Borders, Clear},
+    widgets::{Block, Borders, Clear, Paragraph},
     Frame,
 };
 use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
-use crate::types::Item;
 use crate::types::ItemSize;
+use crate::types::{parse_bin_number, Item, Location, OVERFLOW_BIN};
+use crate::utils::is_read_only;
+use crate::AHResult;
 
-use super::item::{ItemColumn, ItemColumnKind, ItemColumnViewModel, ItemColumnWidth};
+use super::item::{ItemColumn, ItemColumnKind, ItemColumnViewModel, ItemColumnWidth, ItemSortKey};
 use super::sheet::{Row, Sheet, SheetSelection, SheetState};
+use super::theme::Theme;
+
+/// How long the editor waits after the last keypress before saving any pending edits.
+const AUTO_SAVE_DELAY: Duration = Duration::from_secs(10);
 
 lazy_static! {
     static ref ITEM_COLUMNS: Vec<ItemColumn> = vec![
         ItemColumn {
             header: "Location".to_string(),
             width: ItemColumnWidth::Shrink,
-            kind: ItemColumnKind::Choice,
-            display: |i| Ok(i.format().format_location()),
+            kind: ItemColumnKind::LocationChoice,
+            display: |i| Ok(i.location.name.clone()),
             insert_char: None,
             delete_char: None,
             searchable: true,
         },
+        ItemColumn {
+            header: "Bin".to_string(),
+            width: ItemColumnWidth::Shrink,
+            kind: ItemColumnKind::Choice,
+            display: |i| {
+                Ok(if i.location.num_bins > 1 || i.bin_no == OVERFLOW_BIN {
+                    i.bin_no.to_string()
+                } else {
+                    "".to_string()
+                })
+            },
+            insert_char: Some(|item, _, c| {
+                if let Some(digit) = c.to_digit(10) {
+                    let digit = digit as i64;
+
+                    // Typing several digits in a row builds up a multi-digit bin number.
+                    if let Some(bin_no) = [item.bin_no * 10 + digit, digit]
+                        .into_iter()
+                        .find(|b| (1..=item.location.num_bins).contains(b))
+                    {
+                        item.bin_no = bin_no;
+                    }
+                }
+
+                Ok(0)
+            }),
+            delete_char: None,
+            searchable: false,
+        },
         ItemColumn {
             header: "Size".to_string(),
             width: ItemColumnWidth::Shrink,
@@ -54,10 +93,15 @@ lazy_static! {
             insert_char: Some(|item, _, c| {
                 match c.to_ascii_lowercase() {
                     's' | 'm' | 'l' | 'x' => item.size = c.to_ascii_uppercase().to_string(),
-                    _ => {}
+                    '+' | '-' | ' ' => {
+                        if let Ok(size) = item.size.parse::<ItemSize>() {
+                            item.size = size.cycled(if c == '-' { -1 } else { 1 }).to_string();
+                        }
+                    }
+                    _ => bail!("Size must be S/M/L/X"),
                 };
 
-                0
+                Ok(0)
             }),
             delete_char: None,
             searchable: false,
@@ -76,10 +120,11 @@ lazy_static! {
                     c,
                 );
 
-                item.name
+                Ok(item
+                    .name
                     .grapheme_indices(true)
                     .nth(i + 1)
-                    .map_or(item.name.len(), |(offset, _)| offset)
+                    .map_or(item.name.len(), |(offset, _)| offset))
             }),
             delete_char: Some(|item, i| {
                 let (from, to) = {
@@ -100,44 +145,179 @@ lazy_static! {
     ];
 }
 
+struct LocationPicker {
+    rows: Vec<usize>,
+    locations: Vec<Location>,
+    sheet_state: SheetState,
+}
+
+/// Where the editor was scrolled to and which item was selected, so that it can reopen there.
+#[derive(Default)]
+pub struct EditorPosition {
+    offset: usize,
+    row: usize,
+    object_id: Option<i64>,
+}
+
+impl EditorPosition {
+    pub fn load(path: impl AsRef<Path>) -> AHResult<Self> {
+        let path = path.as_ref();
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path)?)
+            .with_context(|| format!("failed to read editor position {}", path.display()))?;
+
+        Ok(Self {
+            offset: contents["offset"].as_u64().unwrap_or(0) as usize,
+            row: contents["row"].as_u64().unwrap_or(0) as usize,
+            object_id: contents["object_id"].as_i64(),
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> AHResult<()> {
+        let contents = json!({
+            "offset": self.offset,
+            "row": self.row,
+            "object_id": self.object_id,
+        });
+
+        std::fs::write(path, serde_json::to_string(&contents)?)?;
+
+        Ok(())
+    }
+}
+
+const NAME_COLUMN: usize = 3;
+
+/// How many rows of the previous page stay in view after paging up or down.
+const PAGE_CONTEXT: usize = 2;
+
 pub struct App<'a, 'b> {
     item_column_view_model: ItemColumnViewModel<'a, 'b>,
     running: Arc<AtomicBool>,
     search: Option<String>,
     search_in_progress: bool,
     sheet_state: SheetState,
-    last_table_size: Option<Rect>,
     last_action_time: Instant,
     action_description: Option<(Instant, String)>,
     help_shown: bool,
+    location_picker: Option<LocationPicker>,
+    location_jump: Option<String>,
+    pending_delete: Option<(Vec<usize>, String)>,
+    pending_quit: bool,
+    command: Option<String>,
+    last_rendered_search: Option<String>,
+    theme: Theme,
 }
 
 impl<'a, 'b> App<'a, 'b> {
-    pub fn new(store: Store, running: Arc<AtomicBool>) -> Self {
+    pub fn new(
+        store: Store,
+        running: Arc<AtomicBool>,
+        theme: Theme,
+        position: EditorPosition,
+    ) -> Self {
         let mut sheet_state = SheetState::default();
-        sheet_state.select(SheetSelection::Char(0, 2, 0));
+        sheet_state.select(SheetSelection::Char(0, NAME_COLUMN, 0));
 
-        Self {
-            item_column_view_model: ItemColumnViewModel::new(store, &*ITEM_COLUMNS),
+        let mut app = Self {
+            item_column_view_model: ItemColumnViewModel::new(
+                store,
+                &ITEM_COLUMNS,
+                theme.search_match,
+            ),
             running,
             search: None,
             search_in_progress: false,
             sheet_state,
-            last_table_size: None,
             last_action_time: Instant::now(),
             action_description: None,
             help_shown: false,
+            location_picker: None,
+            location_jump: None,
+            pending_delete: None,
+            pending_quit: false,
+            command: None,
+            last_rendered_search: None,
+            theme,
+        };
+        app.restore_position(position);
+
+        app
+    }
+
+    /// Selects the remembered item, or the nearest row to where it was if it's gone.
+    fn restore_position(&mut self, position: EditorPosition) {
+        self.item_column_view_model.update(&None).unwrap();
+
+        let len = self.item_column_view_model.rendered_len();
+        if len == 0 {
+            return;
+        }
+
+        let row = position
+            .object_id
+            .and_then(|object_id| self.item_column_view_model.get_row(object_id))
+            .unwrap_or(position.row)
+            .min(len - 1);
+
+        self.sheet_state
+            .select(SheetSelection::Char(row, NAME_COLUMN, 0));
+        self.sheet_state.set_offset(position.offset.min(row));
+    }
+
+    /// Where the editor is now, for `restore_position` next time.
+    pub fn position(&self) -> EditorPosition {
+        let row = self.sheet_state.selection().row().unwrap_or(0);
+
+        EditorPosition {
+            offset: self.sheet_state.get_offset(),
+            row,
+            object_id: self.item_column_view_model.get_object_id(row),
         }
     }
 
     pub fn render_to<B: Backend>(&mut self, f: &mut Frame<'_, B>) {
-        let status = if let Some(search) = &self.search {
-            format!(" - search: \"{}\"", search)
+        self.update_keeping_selected_item();
+
+        let total_len = self.item_column_view_model.total_len();
+        let count = if self.search.as_ref().is_some_and(|s| !s.is_empty()) {
+            format!(
+                "{}/{}",
+                self.item_column_view_model.rendered_len(),
+                total_len
+            )
+        } else {
+            total_len.to_string()
+        };
+        let count = format!("{} item{}", count, if total_len == 1 { "" } else { "s" });
+
+        let status = if let Some(command) = &self.command {
+            format!(" - :{}", command)
+        } else if let Some(search) = &self.search {
+            format!(
+                " - search{}: \"{}\"",
+                if self.item_column_view_model.searches_all_columns() {
+                    " (all columns)"
+                } else {
+                    ""
+                },
+                search
+            )
         } else {
             "".to_string()
         };
 
-        let title = format!("Pachinko{}", status);
+        let title = format!(
+            "Pachinko{} (by {}, {}){}",
+            if is_read_only() { " [read-only]" } else { "" },
+            self.item_column_view_model.sort_key(),
+            count,
+            status
+        );
         let title_width = f.size().width as usize;
         let action_description = if let Some((at, description)) = &self.action_description {
             if Instant::now().saturating_duration_since(*at).as_secs() < 5 {
@@ -150,42 +330,64 @@ impl<'a, 'b> App<'a, 'b> {
         }
         .unwrap_or("F1 for help".to_string());
 
+        // Three spaces surround the title and the action description.
+        let title = truncate_to_width(&title, title_width.saturating_sub(3));
         let outer_frame = Block::default().title(Span::styled(
             format!(
                 " {} {:>width$} ",
                 title,
                 action_description,
-                width = title_width - title.len() - 3,
+                width = title_width.saturating_sub(title.width() + 3),
             ),
             Style::default().add_modifier(Modifier::REVERSED),
         ));
-        let inner_size = outer_frame.inner(f.size());
+        let mut inner_size = outer_frame.inner(f.size());
 
         f.render_widget(outer_frame, f.size());
 
-        self.last_table_size = Some(inner_size);
+        // The help screen already lists every key, so the footer isn't needed while it's open.
+        if !self.help_shown && inner_size.height > 1 {
+            inner_size.height -= 1;
+
+            f.render_widget(
+                Paragraph::new(" F1 help  Alt+Enter add  Alt+S save  F12 quit")
+                    .style(Style::default().add_modifier(Modifier::REVERSED)),
+                Rect {
+                    y: inner_size.bottom(),
+                    height: 1,
+                    ..inner_size
+                },
+            );
+        }
 
-        let (header, column_widths, displayed_rows) =
+        let section_headers = self.item_column_view_model.section_headers();
+        let (header, column_widths, displayed_rows, edited_rows) =
             self.item_column_view_model.render(&self.search).unwrap();
 
         let selected_column = self.sheet_state.selection().column();
 
         f.render_stateful_widget(
             Sheet::new(displayed_rows)
+                .mark_rows("*", edited_rows)
+                .marked_row_style(Style::default().bg(self.theme.marked))
+                .section_headers(
+                    section_headers,
+                    Style::default().add_modifier(Modifier::BOLD),
+                )
                 .highlight_style(
                     Style::default()
                         .add_modifier(Modifier::BOLD)
-                        .bg(Color::Indexed(238)),
+                        .bg(self.theme.highlight),
                 )
                 .highlight_cell_style(
                     Style::default()
                         .add_modifier(Modifier::BOLD)
-                        .bg(Color::Indexed(242)),
+                        .bg(self.theme.cell),
                 )
                 .highlight_i_style(
                     Style::default()
                         .add_modifier(Modifier::REVERSED)
-                        .bg(Color::Indexed(242)),
+                        .bg(self.theme.cursor),
                 )
                 .header(
                     Row::new(header.iter().enumerate().map(|(i, h)| {
@@ -209,6 +411,18 @@ impl<'a, 'b> App<'a, 'b> {
             &mut self.sheet_state,
         );
 
+        if self.item_column_view_model.total_len() == 0 {
+            f.render_widget(
+                Paragraph::new("No items yet - press Alt+Enter to add one")
+                    .alignment(Alignment::Center),
+                Rect {
+                    y: inner_size.y + inner_size.height / 2,
+                    height: 1.min(inner_size.height),
+                    ..inner_size
+                },
+            );
+        }
+
         if self.help_shown {
             let help_frame = Block::default()
                 .title(Span::styled(
@@ -230,28 +444,393 @@ impl<'a, 'b> App<'a, 'b> {
 
             let help_rows: Vec<_> = [
                 &["F1", "Show/hide this help screen"],
+                &[
+                    "F3/Shift+F3",
+                    "Go to the next/previous item matching the last search",
+                ],
                 &["F5", "Refresh the list of items"],
+                &["F6", "Sort by location, name or size"],
+                &["F7", "Search all columns, or just location and name"],
+                &["F8", "Show or hide a header above each location's items"],
                 &["F12", "Quit"],
                 &["Up/Down", "Move between rows"],
                 &["Left/Right", "Move through text"],
                 &["Alt+Left/Right", "Move between columns"],
+                &["+/-/Space", "Change the size of the current item"],
                 &["Alt+Backspace", "Undo the last change"],
-                &["Alt+Delete", "Delete the current item"],
+                &["Alt+Shift+Backspace", "Redo the last undone change"],
+                &[
+                    "Ctrl+Z/Ctrl+Y",
+                    "Undo/redo unsaved typing in the current item",
+                ],
+                &["Space", "Mark or unmark the current row (when no cell is selected)"],
+                &["Alt+Delete", "Delete the current item, or all marked items"],
                 &["Alt+Enter", "Create a new item"],
+                &["Alt+Shift+Enter", "Create a new item above the current one"],
+                &["Alt+D", "Duplicate the current item"],
+                &["Alt+G", "Go to the first item in a location, found by typing part of its name"],
+                &[
+                    "Enter",
+                    "Choose a new location for the current or marked items (in the Location column)",
+                ],
                 &["Alt+S", "Save any changes to the current item"],
                 &["Alt+Shift+S", "Save all changed items"],
+                &[
+                    ":",
+                    "Run a command (sort, goto or add-location) when not editing text",
+                ],
             ]
             .iter()
-            .map(|r| Row::new(r.into_iter().map(|c| c.to_string()).collect::<Vec<_>>()))
+            .map(|r| Row::new(r.iter().map(|c| c.to_string()).collect::<Vec<_>>()))
             .collect();
             f.render_widget(
-                Sheet::new(help_rows.iter()).widths(&[Constraint::Length(16), Constraint::Min(0)]),
+                Sheet::new(help_rows.iter()).widths(&[Constraint::Length(20), Constraint::Min(0)]),
                 help_size.inner(&Margin {
                     horizontal: 1,
                     vertical: 0,
                 }),
             );
         }
+
+        self.render_location_picker(f);
+        self.render_location_jump(f);
+        self.render_delete_confirmation(f);
+        self.render_quit_confirmation(f);
+    }
+
+    fn render_location_picker<B: Backend>(&mut self, f: &mut Frame<'_, B>) {
+        let picker = match &mut self.location_picker {
+            Some(picker) => picker,
+            None => return,
+        };
+
+        let rows: Vec<_> = picker
+            .locations
+            .iter()
+            .map(|l| Row::new(vec![l.name.clone()]))
+            .collect();
+        let width = picker
+            .locations
+            .iter()
+            .map(|l| l.name.len() as u16)
+            .max()
+            .unwrap_or(0)
+            .max(10)
+            + 4;
+        let height = rows.len() as u16 + 2;
+
+        let picker_size = render_popup(f, " Move to ", width, height);
+
+        f.render_stateful_widget(
+            Sheet::new(rows.iter())
+                .widths(&[Constraint::Min(0)])
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+            picker_size.inner(&Margin {
+                horizontal: 1,
+                vertical: 0,
+            }),
+            &mut picker.sheet_state,
+        );
+    }
+
+    fn render_location_jump<B: Backend>(&mut self, f: &mut Frame<'_, B>) {
+        let pattern = match &self.location_jump {
+            Some(pattern) => pattern,
+            None => return,
+        };
+
+        let destination = match self
+            .item_column_view_model
+            .first_row_in_matching_location(pattern)
+        {
+            _ if pattern.is_empty() => "".to_string(),
+            Some((_, location_name)) => location_name,
+            None => "(no match)".to_string(),
+        };
+        let width = (pattern.len().max(destination.len()) as u16).max(20) + 6;
+        let jump_size = render_popup(f, " Go to location ", width, 4);
+
+        f.render_widget(
+            Paragraph::new(format!("> {}\n  {}", pattern, destination)),
+            jump_size.inner(&Margin {
+                horizontal: 1,
+                vertical: 0,
+            }),
+        );
+    }
+
+    fn handle_location_jump(&mut self, code: KeyCode) {
+        let pattern = self.location_jump.as_mut().unwrap();
+
+        match code {
+            KeyCode::Char(c) => pattern.push(c),
+            KeyCode::Backspace => {
+                pattern.pop();
+            }
+            KeyCode::Enter => {
+                let pattern = self.location_jump.take().unwrap();
+
+                let description = match self
+                    .item_column_view_model
+                    .first_row_in_matching_location(&pattern)
+                {
+                    Some((row, location_name)) => {
+                        self.sheet_state.select(SheetSelection::Row(row));
+
+                        format!("went to: {}", location_name)
+                    }
+                    None => format!("no location matches {}", pattern),
+                };
+                self.action_description = Some((Instant::now(), description));
+            }
+            KeyCode::Esc => {
+                self.location_jump = None;
+            }
+            _ => {}
+        }
+    }
+
+    fn render_delete_confirmation<B: Backend>(&mut self, f: &mut Frame<'_, B>) {
+        let (_, item_name) = match &self.pending_delete {
+            Some(pending_delete) => pending_delete,
+            None => return,
+        };
+
+        let message = format!("Delete {}? (y/n)", item_name);
+        let confirmation_size = render_popup(f, " Delete ", message.len() as u16 + 4, 3);
+
+        f.render_widget(
+            Paragraph::new(message),
+            confirmation_size.inner(&Margin {
+                horizontal: 1,
+                vertical: 0,
+            }),
+        );
+    }
+
+    fn handle_delete_confirmation(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => {
+                let (rows, _) = self.pending_delete.take().unwrap();
+                let description = if let [row] = rows[..] {
+                    let item_name = self.item_column_view_model.delete_item(row).unwrap();
+                    format!("deleted: {}", item_name)
+                } else {
+                    let count = self.item_column_view_model.delete_items(&rows).unwrap();
+                    format!("deleted {} items", count)
+                };
+
+                self.sheet_state.clear_marks();
+                self.action_description = Some((Instant::now(), description));
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                self.pending_delete = None;
+            }
+            _ => {}
+        }
+    }
+
+    fn render_quit_confirmation<B: Backend>(&mut self, f: &mut Frame<'_, B>) {
+        if !self.pending_quit {
+            return;
+        }
+
+        let message = "Unsaved changes - save (s), discard (d), cancel (Esc)?";
+        let confirmation_size = render_popup(f, " Quit ", message.len() as u16 + 4, 3);
+
+        f.render_widget(
+            Paragraph::new(message),
+            confirmation_size.inner(&Margin {
+                horizontal: 1,
+                vertical: 0,
+            }),
+        );
+    }
+
+    fn handle_quit_confirmation(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char('s') | KeyCode::Char('S') => {
+                self.item_column_view_model.persist_pending_edits().unwrap();
+                self.running.store(false, Ordering::SeqCst);
+            }
+            KeyCode::Char('d') | KeyCode::Char('D') => {
+                self.running.store(false, Ordering::SeqCst);
+            }
+            KeyCode::Esc => {
+                self.pending_quit = false;
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_command_input(&mut self, code: KeyCode) {
+        let command = self.command.as_mut().unwrap();
+
+        match code {
+            KeyCode::Char(c) => command.push(c),
+            KeyCode::Backspace => {
+                command.pop();
+            }
+            KeyCode::Enter => {
+                let command = self.command.take().unwrap();
+                let description = self
+                    .run_command(&command)
+                    .unwrap_or_else(|e| format!("error: {}", e));
+
+                self.action_description = Some((Instant::now(), description));
+            }
+            KeyCode::Esc => {
+                self.command = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Runs a command typed after `:`, returning a description of what it did.
+    fn run_command(&mut self, command: &str) -> AHResult<String> {
+        let words = shell_words::split(command)?;
+
+        match words.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+            ["add-location", name, num_bins] => {
+                self.item_column_view_model
+                    .add_location(name, parse_bin_number(num_bins)?)?;
+
+                Ok(format!("added location: {}", name))
+            }
+            ["goto", location] => {
+                let row = self
+                    .item_column_view_model
+                    .first_row_in_location(location)
+                    .ok_or_else(|| anyhow!("no items in {}", location))?;
+                self.sheet_state.select(SheetSelection::Row(row));
+
+                Ok(format!("went to: {}", location))
+            }
+            ["sort", sort_key] => {
+                let sort_key = sort_key.parse::<ItemSortKey>()?;
+                self.item_column_view_model.set_sort_key(sort_key);
+
+                Ok(format!("sorted by {}", sort_key))
+            }
+            _ => bail!("unknown command: {}", command),
+        }
+    }
+
+    fn open_location_picker(&mut self, rows: Vec<usize>) {
+        let locations = self.item_column_view_model.locations().unwrap();
+        let current_location = self
+            .item_column_view_model
+            .get_item(rows[0])
+            .map(|i| &i.location);
+
+        let mut sheet_state = SheetState::default();
+        sheet_state.select(SheetSelection::Row(
+            locations
+                .iter()
+                .position(|l| current_location.is_some_and(|c| c.object_id == l.object_id))
+                .unwrap_or(0),
+        ));
+
+        self.location_picker = Some(LocationPicker {
+            rows,
+            locations,
+            sheet_state,
+        });
+    }
+
+    fn handle_location_picker(&mut self, code: KeyCode) {
+        let picker = self.location_picker.as_mut().unwrap();
+        let selected = picker.sheet_state.selection().row().unwrap_or(0);
+
+        match code {
+            KeyCode::Up => picker
+                .sheet_state
+                .select(SheetSelection::Row(selected.saturating_sub(1))),
+            KeyCode::Down => picker.sheet_state.select(SheetSelection::Row(
+                (selected + 1).min(picker.locations.len().saturating_sub(1)),
+            )),
+            KeyCode::Enter => {
+                let picker = self.location_picker.take().unwrap();
+
+                if let Some(location) = picker.locations.get(selected) {
+                    if let [row] = picker.rows[..] {
+                        self.item_column_view_model
+                            .set_location(row, location)
+                            .unwrap();
+                    } else {
+                        let count = self
+                            .item_column_view_model
+                            .move_items(&picker.rows, location)
+                            .unwrap();
+
+                        self.sheet_state.clear_marks();
+                        self.action_description = Some((
+                            Instant::now(),
+                            format!("moved {} items to {}", count, location.name),
+                        ));
+                    }
+                }
+            }
+            KeyCode::Esc => {
+                self.location_picker = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Updates the rows, reselecting the same item if it moved (unless the search changed, which
+    /// resets the selection). Marked items stay marked wherever they move to.
+    fn update_keeping_selected_item(&mut self) {
+        let selected_row = self.sheet_state.selection().row();
+        let selected_object_id = if self.search == self.last_rendered_search {
+            selected_row.and_then(|r| self.item_column_view_model.get_object_id(r))
+        } else {
+            None
+        };
+        let marked_object_ids: Vec<_> = self
+            .sheet_state
+            .marked_rows()
+            .into_iter()
+            .filter_map(|r| self.item_column_view_model.get_object_id(r))
+            .collect();
+
+        self.item_column_view_model.update(&self.search).unwrap();
+        self.last_rendered_search = self.search.clone();
+
+        let item_column_view_model = &self.item_column_view_model;
+        self.sheet_state.set_marked_rows(
+            marked_object_ids
+                .into_iter()
+                .filter_map(|object_id| item_column_view_model.get_row(object_id)),
+        );
+
+        if let (Some(row), Some(object_id)) = (selected_row, selected_object_id) {
+            let new_row = self
+                .item_column_view_model
+                .get_row(object_id)
+                .unwrap_or_else(|| {
+                    row.min(self.item_column_view_model.rendered_len().saturating_sub(1))
+                });
+
+            self.sheet_state.map_selection(|s| s.map_row(|_| new_row));
+        }
+    }
+
+    fn restore_edit(&mut self, row: usize, cell: usize, i: usize, redo: bool) {
+        let restored = if redo {
+            self.item_column_view_model.redo_edit(row)
+        } else {
+            self.item_column_view_model.undo_edit(row)
+        };
+
+        if restored {
+            let len = self
+                .item_column_view_model
+                .get_column_len(row, cell)
+                .unwrap_or(0);
+            self.sheet_state
+                .select(SheetSelection::Char(row, cell, i.min(len)));
+        }
     }
 
     fn reset_idle(&mut self) {
@@ -259,21 +838,68 @@ impl<'a, 'b> App<'a, 'b> {
     }
 
     fn check_idle(&mut self) -> bool {
-        false
+        if self.last_action_time.elapsed() < AUTO_SAVE_DELAY {
+            return false;
+        }
+
+        let count = self.item_column_view_model.persist_pending_edits().unwrap();
+        if count == 0 {
+            return false;
+        }
+
+        self.action_description = Some((Instant::now(), format!("auto-saved {} changes", count)));
+
+        true
     }
 
     pub fn handle(&mut self, ev: Event) -> bool {
         if self.handle_internal(ev) {
             self.reset_idle();
-            return true;
+            true
         } else {
-            return self.check_idle();
+            self.check_idle()
         }
     }
 
     fn handle_internal(&mut self, ev: Event) -> bool {
+        if self.location_picker.is_some()
+            || self.location_jump.is_some()
+            || self.pending_delete.is_some()
+            || self.pending_quit
+            || self.command.is_some()
+        {
+            if let Event::Key(e) = ev {
+                if e.kind == KeyEventKind::Press || e.kind == KeyEventKind::Repeat {
+                    if self.command.is_some() {
+                        self.handle_command_input(e.code);
+                    } else if self.location_jump.is_some() {
+                        self.handle_location_jump(e.code);
+                    } else if self.pending_quit {
+                        self.handle_quit_confirmation(e.code);
+                    } else if self.pending_delete.is_some() {
+                        self.handle_delete_confirmation(e.code);
+                    } else {
+                        self.handle_location_picker(e.code);
+                    }
+                }
+
+                return true;
+            }
+        }
+
         if let Event::Key(ke) = ev {
             if ke.modifiers.contains(KeyModifiers::CONTROL) && ke.kind == KeyEventKind::Press {
+                // Once a Ctrl-search has started, z and y are part of it rather than undo and redo.
+                if let (KeyCode::Char(c @ ('z' | 'y')), SheetSelection::Char(row, cell, i), false) = (
+                    ke.code,
+                    self.sheet_state.selection(),
+                    self.search_in_progress,
+                ) {
+                    self.restore_edit(row, cell, i, c == 'y');
+
+                    return true;
+                }
+
                 if let KeyCode::Char(c) = ke.code {
                     if !self.search_in_progress {
                         self.search = Some("".to_string());
@@ -291,6 +917,10 @@ impl<'a, 'b> App<'a, 'b> {
                 || ke.code == KeyCode::Modifier(ModifierKeyCode::RightControl)
             {
                 match ke.kind {
+                    // While editing text, Ctrl might be the start of Ctrl+Z or Ctrl+Y rather than a
+                    // search.
+                    KeyEventKind::Press
+                        if matches!(self.sheet_state.selection(), SheetSelection::Char(..)) => {}
                     KeyEventKind::Press => {
                         self.search = Some("".to_string());
                         self.search_in_progress = true;
@@ -316,16 +946,52 @@ impl<'a, 'b> App<'a, 'b> {
 
         match ev {
             Event::Key(e) => {
-                if e.kind == KeyEventKind::Press || e.kind == KeyEventKind::Repeat {
+                if (e.kind == KeyEventKind::Press || e.kind == KeyEventKind::Repeat)
+                    && is_read_only()
+                    && self.key_edits(e)
+                {
+                    self.action_description =
+                        Some((Instant::now(), "store opened read-only".to_string()));
+                } else if e.kind == KeyEventKind::Press || e.kind == KeyEventKind::Repeat {
                     match e.code {
                         KeyCode::F(1) => {
                             self.help_shown = !self.help_shown;
                         }
+                        KeyCode::F(3) => {
+                            if let Some(row) = self.item_column_view_model.find_next_row(
+                                self.sheet_state.selection().row().unwrap_or(0),
+                                e.modifiers.contains(KeyModifiers::SHIFT),
+                            ) {
+                                self.sheet_state
+                                    .select(SheetSelection::Char(row, NAME_COLUMN, 0));
+                            }
+                        }
                         KeyCode::F(5) => {
                             self.item_column_view_model.refresh().unwrap();
                         }
+                        KeyCode::F(6) => {
+                            self.item_column_view_model.cycle_sort_key();
+                        }
+                        KeyCode::F(7) => {
+                            self.item_column_view_model.toggle_search_all_columns();
+                        }
+                        KeyCode::F(8) => {
+                            self.item_column_view_model.toggle_group_by_location();
+                        }
                         KeyCode::F(12) => {
-                            self.running.store(false, Ordering::SeqCst);
+                            if self.item_column_view_model.has_pending_edits() {
+                                self.pending_quit = true;
+                            } else {
+                                self.running.store(false, Ordering::SeqCst);
+                            }
+                        }
+                        KeyCode::Backspace
+                            if e.modifiers == KeyModifiers::ALT | KeyModifiers::SHIFT =>
+                        {
+                            if let Some(description) = self.item_column_view_model.redo().unwrap() {
+                                self.action_description =
+                                    Some((Instant::now(), format!("redid {}", description)));
+                            }
                         }
                         KeyCode::Backspace if e.modifiers == KeyModifiers::ALT => {
                             if let Some(description) = self.item_column_view_model.undo().unwrap() {
@@ -333,31 +999,55 @@ impl<'a, 'b> App<'a, 'b> {
                                     Some((Instant::now(), format!("undid {}", description)));
                             }
                         }
+                        KeyCode::Enter
+                            if e.modifiers == KeyModifiers::ALT | KeyModifiers::SHIFT =>
+                        {
+                            self.insert_item(true);
+                        }
                         KeyCode::Enter if e.modifiers == KeyModifiers::ALT => {
-                            self.item_column_view_model
-                                .insert_item(
-                                    self.sheet_state.selection().row().unwrap_or(0),
-                                    &self.search,
-                                )
-                                .unwrap();
-
-                            self.sheet_state
-                                .map_selection(|s| s.map_row_or(0, |r| r + 1));
+                            self.insert_item(false);
                         }
-                        KeyCode::Delete if e.modifiers == KeyModifiers::ALT => {
-                            if let Some(row) = self.sheet_state.selection().row() {
-                                let item_name =
-                                    self.item_column_view_model.delete_item(row).unwrap();
+                        KeyCode::Char('g') if e.modifiers == KeyModifiers::ALT => {
+                            self.location_jump = Some("".to_string());
+                        }
+                        KeyCode::Char('d') if e.modifiers == KeyModifiers::ALT => {
+                            if let Some(row) = self
+                                .sheet_state
+                                .selection()
+                                .row()
+                                .filter(|r| self.item_column_view_model.get_item(*r).is_some())
+                            {
+                                let name = self.item_column_view_model.duplicate_item(row).unwrap();
+
+                                self.sheet_state.map_selection(|s| s.map_row(|r| r + 1));
                                 self.action_description =
-                                    Some((Instant::now(), format!("deleted: {}", item_name)));
+                                    Some((Instant::now(), format!("added: {}", name)));
+                            }
+                        }
+                        KeyCode::Enter => {
+                            if let SheetSelection::Cell(row, cell) = self.sheet_state.selection() {
+                                if self.item_column_view_model.column_picks_location(cell)
+                                    && self.item_column_view_model.get_item(row).is_some()
+                                {
+                                    let marked_rows = self.sheet_state.marked_rows();
+                                    self.open_location_picker(if marked_rows.is_empty() {
+                                        vec![row]
+                                    } else {
+                                        marked_rows
+                                    });
+                                }
                             }
                         }
                         KeyCode::Delete if e.modifiers == KeyModifiers::ALT => {
-                            if let Some(row) = self.sheet_state.selection().row() {
-                                let item_name =
-                                    self.item_column_view_model.delete_item(row).unwrap();
-                                self.action_description =
-                                    Some((Instant::now(), format!("deleted: {}", item_name)));
+                            let marked_rows = self.sheet_state.marked_rows();
+
+                            if !marked_rows.is_empty() {
+                                let description = format!("{} marked items", marked_rows.len());
+                                self.pending_delete = Some((marked_rows, description));
+                            } else if let Some(row) = self.sheet_state.selection().row() {
+                                if let Some(item) = self.item_column_view_model.get_item(row) {
+                                    self.pending_delete = Some((vec![row], item.name.clone()));
+                                }
                             }
                         }
                         KeyCode::Char('s')
@@ -396,14 +1086,10 @@ impl<'a, 'b> App<'a, 'b> {
                             self.back_out();
                         }
                         KeyCode::PageUp => {
-                            if let Some(table_size) = self.last_table_size {
-                                self.scroll_up((table_size.height as usize).saturating_sub(3));
-                            }
+                            self.sheet_state.page_up(PAGE_CONTEXT);
                         }
                         KeyCode::PageDown => {
-                            if let Some(table_size) = self.last_table_size {
-                                self.scroll_down((table_size.height as usize).saturating_sub(3));
-                            }
+                            self.sheet_state.page_down(PAGE_CONTEXT);
                         }
                         // KeyCode::Enter if e.modifiers.contains(KeyModifiers::SHIFT) => {
                         //     self.insert_item();
@@ -438,6 +1124,21 @@ impl<'a, 'b> App<'a, 'b> {
                                 self.item_column_view_model.delete_char(row, cell, i);
                             }
                         }
+                        KeyCode::Char(' ') if self.row_selected() => {
+                            if let Some(row) = self.sheet_state.selection().row() {
+                                self.sheet_state.toggle_mark(row);
+                                self.move_down();
+                            }
+                        }
+                        // Outside of text, there's nothing else for `:` to do.
+                        KeyCode::Char(':')
+                            if !matches!(
+                                self.sheet_state.selection(),
+                                SheetSelection::Char(..)
+                            ) =>
+                        {
+                            self.command = Some("".to_string());
+                        }
                         KeyCode::Char(orig_c) => {
                             let c = if e.modifiers.contains(KeyModifiers::SHIFT) {
                                 orig_c.to_ascii_uppercase()
@@ -445,18 +1146,25 @@ impl<'a, 'b> App<'a, 'b> {
                                 orig_c
                             };
 
-                            match self.sheet_state.selection() {
-                                SheetSelection::Char(row, cell, i) => {
-                                    let new_i =
-                                        self.item_column_view_model.insert_char(row, cell, i, c);
+                            let (row, cell, i) = match self.sheet_state.selection() {
+                                SheetSelection::Char(row, cell, i) => (row, cell, Some(i)),
+                                SheetSelection::Cell(row, cell) => (row, cell, None),
+                                _ => return true,
+                            };
 
-                                    self.sheet_state
-                                        .select(SheetSelection::Char(row, cell, new_i));
+                            match self.item_column_view_model.insert_char(
+                                row,
+                                cell,
+                                i.unwrap_or(0),
+                                c,
+                            ) {
+                                Ok(new_i) if i.is_some() => self
+                                    .sheet_state
+                                    .select(SheetSelection::Char(row, cell, new_i)),
+                                Ok(_) => {}
+                                Err(e) => {
+                                    self.action_description = Some((Instant::now(), e.to_string()))
                                 }
-                                SheetSelection::Cell(row, cell) => {
-                                    self.item_column_view_model.insert_char(row, cell, 0, c);
-                                }
-                                _ => {}
                             }
                         }
                         _ => {
@@ -472,6 +1180,20 @@ impl<'a, 'b> App<'a, 'b> {
                 crossterm::event::MouseEventKind::ScrollDown => {
                     self.scroll_down(3);
                 }
+                crossterm::event::MouseEventKind::Down(crossterm::event::MouseButton::Left) => {
+                    // Clicks would otherwise change which item an open popup acts on.
+                    if self.help_shown
+                        || self.location_picker.is_some()
+                        || self.location_jump.is_some()
+                        || self.pending_delete.is_some()
+                        || self.pending_quit
+                        || self.command.is_some()
+                    {
+                        return false;
+                    }
+
+                    self.select_at(e.column, e.row);
+                }
                 _ => {
                     return false;
                 }
@@ -485,19 +1207,50 @@ impl<'a, 'b> App<'a, 'b> {
         true
     }
 
+    /// Whether the key would change items (or the store), which a read-only store doesn't allow.
+    fn key_edits(&self, e: KeyEvent) -> bool {
+        match e.code {
+            KeyCode::Backspace | KeyCode::Delete => true,
+            KeyCode::Enter => {
+                e.modifiers.contains(KeyModifiers::ALT)
+                    || matches!(self.sheet_state.selection(), SheetSelection::Cell(..))
+            }
+            KeyCode::Char(':') => matches!(self.sheet_state.selection(), SheetSelection::Char(..)),
+            KeyCode::Char(' ') => !self.row_selected(),
+            KeyCode::Char('g') if e.modifiers == KeyModifiers::ALT => false,
+            KeyCode::Char(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Whether a whole row is selected, rather than a cell in it.
+    fn row_selected(&self) -> bool {
+        matches!(self.sheet_state.selection(), SheetSelection::Row(_))
+    }
+
     pub fn handle_idle(&mut self) -> bool {
         self.check_idle()
     }
 
+    // Moving up from the first row or down from the last wraps around to the other end.
     fn move_up(&mut self) {
         use SheetSelection::*;
 
         let default_row = self.sheet_state.get_offset();
+        let num_rows = self.item_column_view_model.rendered_len();
+        let up = |r: usize| {
+            if r == 0 {
+                num_rows.saturating_sub(1)
+            } else {
+                r.min(num_rows) - 1
+            }
+        };
+
         self.sheet_state.map_selection(|s| match s {
             None => Row(default_row),
-            Row(r) => Row(r.saturating_sub(1)),
-            Cell(r, c) => Cell(r.saturating_sub(1), c),
-            Char(r, c, _) => Char(r.saturating_sub(1), c, 0),
+            Row(r) => Row(up(r)),
+            Cell(r, c) => Cell(up(r), c),
+            Char(r, c, _) => Char(up(r), c, 0),
         });
     }
 
@@ -505,11 +1258,14 @@ impl<'a, 'b> App<'a, 'b> {
         use SheetSelection::*;
 
         let default_row = self.sheet_state.get_offset();
+        let num_rows = self.item_column_view_model.rendered_len();
+        let down = |r: usize| if r + 1 >= num_rows { 0 } else { r + 1 };
+
         self.sheet_state.map_selection(|s| match s {
             None => Row(default_row),
-            Row(r) => Row(r + 1),
-            Cell(r, c) => Cell(r + 1, c),
-            Char(r, c, _) => Char(r + 1, c, 0),
+            Row(r) => Row(down(r)),
+            Cell(r, c) => Cell(down(r), c),
+            Char(r, c, _) => Char(down(r), c, 0),
         });
     }
 
@@ -541,12 +1297,62 @@ impl<'a, 'b> App<'a, 'b> {
         });
     }
 
+    fn insert_item(&mut self, before: bool) {
+        let was_empty = self.item_column_view_model.rendered_len() == 0;
+
+        if let Err(e) = self.item_column_view_model.insert_item(
+            self.sheet_state.selection().row().unwrap_or(0),
+            before,
+            &self.search,
+        ) {
+            self.action_description = Some((Instant::now(), format!("error: {}", e)));
+            return;
+        }
+
+        if was_empty {
+            self.reset_selection();
+        } else if before {
+            // The new item takes the current row's place.
+            self.sheet_state.map_selection(|s| s.map_row_or(0, |r| r));
+        } else {
+            self.sheet_state
+                .map_selection(|s| s.map_row_or(0, |r| r + 1));
+        }
+    }
+
+    fn select_at(&mut self, x: u16, y: u16) {
+        if let Some((row, column, i)) = self.sheet_state.cell_at(x, y) {
+            self.sheet_state.select(
+                if self
+                    .item_column_view_model
+                    .column_allows_char_selection(column)
+                {
+                    let len = self
+                        .item_column_view_model
+                        .get_column_len(row, column)
+                        .unwrap_or(0);
+
+                    SheetSelection::Char(row, column, i.min(len))
+                } else {
+                    SheetSelection::Cell(row, column)
+                },
+            );
+        }
+    }
+
     fn reset_selection(&mut self) {
-        self.sheet_state.select(SheetSelection::Char(0, 2, 0));
+        self.sheet_state
+            .select(SheetSelection::Char(0, NAME_COLUMN, 0));
     }
 
     fn back_out(&mut self) {
         use SheetSelection::*;
+
+        // Backing out of a row lets go of any marks along with it.
+        if matches!(self.sheet_state.selection(), None | Row(_)) {
+            self.sheet_state.clear_marks();
+        }
+
         self.sheet_state.map_selection(|s| match s {
             None | Row(_) => None,
             Cell(r, _) | Char(r, _, _) => Row(r),
@@ -603,3 +1409,111 @@ impl<'a, 'b> App<'a, 'b> {
         self.sheet_state.scroll_down(delta)
     }
 }
+
+/// Cuts the text down to at most `max_width` columns, ending it with `…` if anything was cut.
+fn truncate_to_width(text: &str, max_width: usize) -> String {
+    if text.width() <= max_width {
+        return text.to_string();
+    }
+
+    let mut truncated = String::new();
+    for grapheme in text.graphemes(true) {
+        if truncated.width() + grapheme.width() + 1 > max_width {
+            break;
+        }
+
+        truncated.push_str(grapheme);
+    }
+
+    if max_width > 0 {
+        truncated.push('…');
+    }
+
+    truncated
+}
+
+/// Draws a bordered, centered popup over the rest of the screen and returns the area inside it.
+fn render_popup<B: Backend>(f: &mut Frame<'_, B>, title: &str, width: u16, height: u16) -> Rect {
+    let size = f.size();
+    let popup_frame_size = Rect {
+        x: size.width.saturating_sub(width) / 2,
+        y: size.height.saturating_sub(height) / 2,
+        width: width.min(size.width),
+        height: height.min(size.height),
+    };
+    let popup_frame = Block::default()
+        .title(Span::styled(
+            title.to_string(),
+            Style::default()
+                .bg(Color::Black)
+                .add_modifier(Modifier::REVERSED),
+        ))
+        .borders(Borders::ALL);
+    let popup_size = popup_frame.inner(popup_frame_size);
+
+    f.render_widget(Clear, popup_frame_size);
+    f.render_widget(popup_frame, popup_frame_size);
+
+    popup_size
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use qualia::{object, Object};
+    use tempfile::Builder;
+
+    #[test]
+    fn truncating_text_counts_display_width() {
+        assert_eq!(truncate_to_width("Pachinko", 20), "Pachinko");
+        assert_eq!(truncate_to_width("Pachinko", 5), "Pach…");
+        assert_eq!(truncate_to_width("日本語の棚", 7), "日本語…");
+        assert_eq!(truncate_to_width("Pachinko", 0), "");
+    }
+
+    #[test]
+    fn ctrl_search_keeps_z_and_y_after_it_starts() {
+        let temp_dir = Builder::new().prefix("pachinko-editor").tempdir().unwrap();
+        let mut store = Store::open(temp_dir.path().join("pachinko-test-store.qualia")).unwrap();
+
+        let checkpoint = store.checkpoint().unwrap();
+        let location_id = checkpoint
+            .add(object!("type" => "location", "name" => "Test", "num_bins" => 1))
+            .unwrap();
+        checkpoint
+            .add(object!(
+                "type" => "item",
+                "name" => "Pizza",
+                "location_id" => location_id,
+                "bin_no" => 1,
+                "size" => "S",
+            ))
+            .unwrap();
+        checkpoint.commit("add test items").unwrap();
+
+        let mut app = App::new(
+            store,
+            Arc::new(AtomicBool::new(true)),
+            Theme::default(),
+            EditorPosition::default(),
+        );
+
+        app.handle(Event::Key(KeyEvent::new(
+            KeyCode::Char('X'),
+            KeyModifiers::NONE,
+        )));
+        for c in "pizza".chars() {
+            app.handle(Event::Key(KeyEvent::new(
+                KeyCode::Char(c),
+                KeyModifiers::CONTROL,
+            )));
+        }
+
+        assert_eq!(app.search.as_deref(), Some("pizza"));
+        assert_eq!(
+            app.item_column_view_model.get_item(0).unwrap().name,
+            "XPizza"
+        );
+    }
+}
@@ -1,12 +1,13 @@
 use std::{
-    collections::{HashSet},
+    collections::{HashMap, HashSet},
     vec,
 };
 
+use anyhow::bail;
 use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
 use indexmap::IndexMap;
 
-use qualia::{CheckpointId, ObjectShapeWithId, Queryable, Store};
+use qualia::{object, CheckpointId, Object, ObjectShapeWithId, Queryable, Store, Q};
 use tui::{
     layout::Constraint,
     style::{Color, Style},
@@ -14,10 +15,16 @@ use tui::{
 };
 use unicode_segmentation::UnicodeSegmentation;
 
-use crate::{types::Item, utils::add_item};
-use crate::{types::ItemSize, AHResult};
+use crate::{history::RedoStack, types::ItemSize, AHResult};
+use crate::{
+    types::{Item, Location, OVERFLOW_BIN},
+    utils::{
+        add_item, begin_checkpoint, bin_fullnesses, choose_bin, commit, emptiest_bin,
+        item_fullness, size_weights, CapacityCheck, FillOrder,
+    },
+};
 
-use super::sheet::Row;
+use super::sheet::{Cell, Row};
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ItemColumnWidth {
@@ -28,24 +35,29 @@ pub enum ItemColumnWidth {
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ItemColumnKind {
     Choice,
+    /// Picked from a list of existing locations.
+    LocationChoice,
     FullText,
 }
 
+/// Types a character at the given position, returning where the cursor goes next, or why the
+/// character can't be typed here.
+pub type InsertChar = fn(&mut Item, usize, char) -> AHResult<usize>;
+
 pub struct ItemColumn {
     pub header: String,
     pub width: ItemColumnWidth,
     pub kind: ItemColumnKind,
     pub display: fn(&Item) -> AHResult<String>,
-    pub insert_char: Option<fn(&mut Item, usize, char) -> usize>,
+    pub insert_char: Option<InsertChar>,
     pub delete_char: Option<fn(&mut Item, usize)>,
     pub searchable: bool,
 }
 
-fn render_item_columns(columns: &Vec<ItemColumn>, item: &Item) -> (Vec<String>, Vec<usize>) {
+fn render_item_columns(columns: &[ItemColumn], item: &Item) -> (Vec<String>, Vec<usize>) {
     columns
         .iter()
-        .enumerate()
-        .map(|(_, c)| {
+        .map(|c| {
             let content = (c.display)(item).unwrap_or("".into());
             let width = content.graphemes(true).count();
             (content, width)
@@ -53,6 +65,25 @@ fn render_item_columns(columns: &Vec<ItemColumn>, item: &Item) -> (Vec<String>,
         .unzip()
 }
 
+/// Builds the row for an item, marking its location in red if its bin is outside the location.
+fn render_item_row<'row, C: Into<Cell<'row>>>(
+    columns: &[ItemColumn],
+    item: &Item,
+    contents: impl IntoIterator<Item = C>,
+) -> Row<'row> {
+    let bin_is_invalid = item.bin_no < OVERFLOW_BIN || item.bin_no > item.location.num_bins;
+
+    Row::new(columns.iter().zip(contents).map(|(column, content)| {
+        let cell = content.into();
+
+        if bin_is_invalid && column.kind == ItemColumnKind::LocationChoice {
+            cell.style(Style::default().fg(Color::Red))
+        } else {
+            cell
+        }
+    }))
+}
+
 fn item_name_from_search(search: &Option<String>) -> String {
     if let Some(search) = search {
         let (word_indices, words): (Vec<_>, Vec<_>) = search.split_word_bound_indices().unzip();
@@ -92,35 +123,123 @@ impl<T> std::cmp::PartialEq for ItemRenderEntry<T> {
 }
 impl<T> std::cmp::Eq for ItemRenderEntry<T> {}
 
-impl<C> std::cmp::Ord for ItemRenderEntry<C> {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        (&self.item.location.name, self.item.bin_no, &self.item.name).cmp(&(
-            &other.item.location.name,
-            other.item.bin_no,
-            &other.item.name,
-        ))
+impl<C> ItemRenderEntry<C> {
+    fn cmp_by<D>(&self, other: &ItemRenderEntry<D>, sort_key: ItemSortKey) -> std::cmp::Ordering {
+        fn location_bin_name(i: &Item) -> (&str, i64, &str) {
+            (&i.location.name, i.bin_no, &i.name)
+        }
+        fn size(i: &Item) -> Option<ItemSize> {
+            i.size.parse().ok()
+        }
+
+        match sort_key {
+            ItemSortKey::LocationBinName => {
+                location_bin_name(&self.item).cmp(&location_bin_name(&other.item))
+            }
+            ItemSortKey::Name => self.item.name.cmp(&other.item.name),
+            ItemSortKey::Size => size(&self.item).cmp(&size(&other.item)),
+        }
+        .then_with(|| location_bin_name(&self.item).cmp(&location_bin_name(&other.item)))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ItemSortKey {
+    #[default]
+    LocationBinName,
+    Name,
+    Size,
+}
+
+impl ItemSortKey {
+    pub fn next(self) -> Self {
+        match self {
+            Self::LocationBinName => Self::Name,
+            Self::Name => Self::Size,
+            Self::Size => Self::LocationBinName,
+        }
     }
 }
-impl<C> std::cmp::PartialOrd for ItemRenderEntry<C> {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+
+impl std::str::FromStr for ItemSortKey {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> AHResult<Self> {
+        match s.to_ascii_lowercase().as_ref() {
+            "location" => Ok(Self::LocationBinName),
+            "name" => Ok(Self::Name),
+            "size" => Ok(Self::Size),
+            _ => bail!("sort must be location, name or size"),
+        }
     }
 }
 
+impl std::fmt::Display for ItemSortKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        f.write_str(match self {
+            Self::LocationBinName => "location",
+            Self::Name => "name",
+            Self::Size => "size",
+        })
+    }
+}
+
+/// Lowercases the text and strips accents from Latin letters, so that "cafe" matches "Café". Each
+/// character maps to exactly one character, so match positions still line up with the original.
+fn fold_for_search(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            let mut lowercase = c.to_lowercase();
+            let c = match (lowercase.next(), lowercase.next()) {
+                (Some(l), None) => l,
+                _ => c,
+            };
+
+            match c {
+                'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => 'a',
+                'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => 'c',
+                'ď' | 'đ' => 'd',
+                'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => 'e',
+                'ĝ' | 'ğ' | 'ġ' | 'ģ' => 'g',
+                'ĥ' | 'ħ' => 'h',
+                'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => 'i',
+                'ĵ' => 'j',
+                'ķ' => 'k',
+                'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => 'l',
+                'ñ' | 'ń' | 'ņ' | 'ň' => 'n',
+                'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => 'o',
+                'ŕ' | 'ŗ' | 'ř' => 'r',
+                'ś' | 'ŝ' | 'ş' | 'š' => 's',
+                'ţ' | 'ť' | 'ŧ' => 't',
+                'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => 'u',
+                'ŵ' => 'w',
+                'ý' | 'ÿ' | 'ŷ' => 'y',
+                'ź' | 'ż' | 'ž' => 'z',
+                c => c,
+            }
+        })
+        .collect()
+}
+
 struct ItemColumnRenderedSet<'columns, 'row> {
     columns: &'columns Vec<ItemColumn>,
     checkpoint: CheckpointId,
     entries: IndexMap<i64, ItemRenderEntry<Row<'row>>>,
     search: Option<String>,
+    search_all_columns: bool,
+    sort_key: ItemSortKey,
+    match_color: Color,
 }
 
 impl<'columns, 'row> ItemColumnRenderedSet<'columns, 'row> {
-    fn new(columns: &'columns Vec<ItemColumn>) -> Self {
+    fn new(columns: &'columns Vec<ItemColumn>, match_color: Color) -> Self {
         Self {
             columns,
+            match_color,
             checkpoint: 0,
             entries: IndexMap::new(),
             search: None,
+            search_all_columns: false,
+            sort_key: ItemSortKey::default(),
         }
     }
 
@@ -129,8 +248,14 @@ impl<'columns, 'row> ItemColumnRenderedSet<'columns, 'row> {
         last_fetched_items: &IndexMap<i64, Item>,
         last_updated_checkpoint: CheckpointId,
         search: Option<String>,
+        search_all_columns: bool,
+        sort_key: ItemSortKey,
     ) {
-        if search == self.search && last_updated_checkpoint == self.checkpoint {
+        if search == self.search
+            && search_all_columns == self.search_all_columns
+            && last_updated_checkpoint == self.checkpoint
+            && sort_key == self.sort_key
+        {
             return;
         }
 
@@ -154,11 +279,12 @@ impl<'columns, 'row> ItemColumnRenderedSet<'columns, 'row> {
             })
             .collect();
 
-        all_entries.sort_by(|_, a, _, b| a.cmp(b));
+        all_entries.sort_by(|_, a, _, b| a.cmp_by(b, sort_key));
 
         let (mut filtered_entries, mut unused_entries): (IndexMap<_, _>, IndexMap<_, _>) =
             if let Some(search) = non_empty_search {
-                let matcher = SkimMatcherV2::default();
+                let matcher = SkimMatcherV2::default().ignore_case();
+                let search = fold_for_search(search);
 
                 let mut unused_entries = IndexMap::new();
 
@@ -170,11 +296,11 @@ impl<'columns, 'row> ItemColumnRenderedSet<'columns, 'row> {
                             .iter()
                             .enumerate()
                             .map(|(i, c)| {
-                                if !self.columns[i].searchable {
+                                if !self.columns[i].searchable && !search_all_columns {
                                     return (c, 0, vec![]);
                                 }
 
-                                match matcher.fuzzy_indices(&c, search) {
+                                match matcher.fuzzy_indices(&fold_for_search(c), &search) {
                                     None => (c, 0, vec![]),
                                     Some((score, indices)) => (c, score, indices),
                                 }
@@ -193,21 +319,23 @@ impl<'columns, 'row> ItemColumnRenderedSet<'columns, 'row> {
                             total_score,
                             object_id,
                             ItemRenderEntry {
-                                contents: Row::new(column_results.into_iter().map(
-                                    |(c, _, indices)| {
+                                contents: render_item_row(
+                                    self.columns,
+                                    &e.item,
+                                    column_results.into_iter().map(|(c, _, indices)| {
                                         let mut spans: Vec<_> =
                                             c.chars().map(|c| Span::raw(c.to_string())).collect();
 
                                         for idx in &indices {
                                             spans[*idx] = Span::styled(
                                                 spans[*idx].content.clone(),
-                                                Style::default().bg(Color::Indexed(58)),
+                                                Style::default().bg(self.match_color),
                                             );
                                         }
 
                                         Spans::from(spans)
-                                    },
-                                )),
+                                    }),
+                                ),
                                 item: e.item,
                                 column_widths: e.column_widths,
                             },
@@ -232,7 +360,7 @@ impl<'columns, 'row> ItemColumnRenderedSet<'columns, 'row> {
                             (
                                 object_id,
                                 ItemRenderEntry {
-                                    contents: Row::new(e.contents),
+                                    contents: render_item_row(self.columns, &e.item, e.contents),
                                     item: e.item,
                                     column_widths: e.column_widths,
                                 },
@@ -243,7 +371,8 @@ impl<'columns, 'row> ItemColumnRenderedSet<'columns, 'row> {
                 )
             };
 
-        let reordered_entries = if self.entries.is_empty() {
+        // A new sort order means the old order is no longer worth keeping.
+        let reordered_entries = if self.entries.is_empty() || sort_key != self.sort_key {
             filtered_entries
         } else {
             // First, build the list of new items using the order of the old items.
@@ -259,7 +388,11 @@ impl<'columns, 'row> ItemColumnRenderedSet<'columns, 'row> {
                                 (
                                     *id,
                                     ItemRenderEntry {
-                                        contents: Row::new(e.contents),
+                                        contents: render_item_row(
+                                            self.columns,
+                                            &e.item,
+                                            e.contents,
+                                        ),
                                         item: e.item,
                                         column_widths: e.column_widths,
                                     },
@@ -277,8 +410,8 @@ impl<'columns, 'row> ItemColumnRenderedSet<'columns, 'row> {
                 let insert_pos = reordered_entries
                     .values()
                     .collect::<Vec<_>>()
-                    .binary_search(&&entry)
-                    .map_or_else(|e| e, |o| o);
+                    .binary_search_by(|e| e.cmp_by(&entry, sort_key))
+                    .unwrap_or_else(|e| e);
                 reordered_entries.insert(object_id, entry);
                 reordered_entries.move_index(reordered_entries.len() - 1, insert_pos);
             }
@@ -289,6 +422,8 @@ impl<'columns, 'row> ItemColumnRenderedSet<'columns, 'row> {
         self.checkpoint = last_updated_checkpoint;
         self.entries = reordered_entries;
         self.search = search;
+        self.search_all_columns = search_all_columns;
+        self.sort_key = sort_key;
     }
 
     fn max_column_width(&self, column: usize) -> usize {
@@ -298,19 +433,19 @@ impl<'columns, 'row> ItemColumnRenderedSet<'columns, 'row> {
             .unwrap()
     }
 
-    fn add_item(&mut self, after_index: usize, item: &Item) {
+    fn add_item(&mut self, index: usize, item: &Item) {
         let (column_contents, column_widths) = render_item_columns(self.columns, item);
 
         let (inserted_index, _) = self.entries.insert_full(
             item.get_object_id().unwrap(),
             ItemRenderEntry {
                 item: item.clone(),
-                contents: Row::new(column_contents),
+                contents: render_item_row(self.columns, item, column_contents),
                 column_widths,
             },
         );
 
-        self.entries.move_index(inserted_index, after_index + 1);
+        self.entries.move_index(inserted_index, index);
     }
 
     fn edit_item<T>(&mut self, index: usize, editor: impl FnOnce(&mut Item) -> T) -> (i64, T) {
@@ -319,12 +454,22 @@ impl<'columns, 'row> ItemColumnRenderedSet<'columns, 'row> {
         let value = editor(&mut entry.item);
         let (column_contents, column_widths) = render_item_columns(self.columns, &entry.item);
         entry.column_widths = column_widths;
-        entry.contents = Row::new(column_contents);
+        entry.contents = render_item_row(self.columns, &entry.item, column_contents);
 
         (*object_id, value)
     }
 }
 
+/// Headers, column widths, rows and which of those rows have unsaved edits.
+type RenderedRows<'a> = (Vec<String>, Vec<Constraint>, Vec<&'a Row<'a>>, Vec<bool>);
+
+/// Unsaved states of an item, for undoing and redoing keystrokes before they are persisted.
+#[derive(Default)]
+struct EditHistory {
+    undo: Vec<Item>,
+    redo: Vec<Item>,
+}
+
 pub struct ItemColumnViewModel<'columns, 'row> {
     store: Store,
     last_fetched_items: IndexMap<i64, Item>,
@@ -332,22 +477,39 @@ pub struct ItemColumnViewModel<'columns, 'row> {
     last_updated_checkpoint: CheckpointId,
     last_rendered_set: ItemColumnRenderedSet<'columns, 'row>,
     edited_items: HashSet<i64>,
+    edit_histories: HashMap<i64, EditHistory>,
+    redo_stack: RedoStack,
+    last_search: Option<String>,
+    search_all_columns: bool,
+    sort_key: ItemSortKey,
+    group_by_location: bool,
 }
 
 impl<'columns, 'row> ItemColumnViewModel<'columns, 'row> {
-    pub fn new(store: Store, columns: &'columns Vec<ItemColumn>) -> Self {
+    pub fn new(store: Store, columns: &'columns Vec<ItemColumn>, match_color: Color) -> Self {
         Self {
             store,
             columns,
             last_fetched_items: IndexMap::new(),
             last_updated_checkpoint: 0,
-            last_rendered_set: ItemColumnRenderedSet::new(&columns),
+            last_rendered_set: ItemColumnRenderedSet::new(columns, match_color),
             edited_items: HashSet::new(),
+            edit_histories: HashMap::new(),
+            redo_stack: RedoStack::default(),
+            last_search: None,
+            search_all_columns: false,
+            sort_key: ItemSortKey::default(),
+            group_by_location: false,
         }
     }
 
+    /// The store's last checkpoint, or 0 for a new store that doesn't have any yet.
+    fn last_checkpoint_id(&self) -> CheckpointId {
+        self.store.last_checkpoint_id().unwrap_or(0)
+    }
+
     pub fn refresh(&mut self) -> AHResult<()> {
-        self.last_updated_checkpoint = self.store.last_checkpoint_id()?;
+        self.last_updated_checkpoint = self.last_checkpoint_id();
 
         self.last_fetched_items = self
             .store
@@ -360,7 +522,7 @@ impl<'columns, 'row> ItemColumnViewModel<'columns, 'row> {
     }
 
     fn refresh_if_needed(&mut self) -> AHResult<bool> {
-        if self.store.modified_since(self.last_updated_checkpoint)? {
+        if self.last_checkpoint_id() != self.last_updated_checkpoint {
             self.refresh()?;
             Ok(true)
         } else {
@@ -368,17 +530,28 @@ impl<'columns, 'row> ItemColumnViewModel<'columns, 'row> {
         }
     }
 
-    pub fn render(
-        &mut self,
-        search: &Option<String>,
-    ) -> AHResult<(Vec<String>, Vec<Constraint>, Vec<&Row>)> {
+    /// Brings the rendered rows up to date with the store and search, without rendering them.
+    pub fn update(&mut self, search: &Option<String>) -> AHResult<()> {
         self.refresh_if_needed()?;
+
+        if search.as_ref().is_some_and(|s| !s.is_empty()) {
+            self.last_search = search.clone();
+        }
+
         self.last_rendered_set.regenerate_if_needed(
             &self.last_fetched_items,
             self.last_updated_checkpoint,
             search.clone(),
+            self.search_all_columns,
+            self.sort_key,
         );
 
+        Ok(())
+    }
+
+    pub fn render(&mut self, search: &Option<String>) -> AHResult<RenderedRows<'_>> {
+        self.update(search)?;
+
         Ok((
             self.columns.iter().map(|c| c.header.clone()).collect(),
             self.columns
@@ -398,6 +571,11 @@ impl<'columns, 'row> ItemColumnViewModel<'columns, 'row> {
                 .values()
                 .map(|e| &e.contents)
                 .collect(),
+            self.last_rendered_set
+                .entries
+                .keys()
+                .map(|object_id| self.edited_items.contains(object_id))
+                .collect(),
         ))
     }
 
@@ -415,29 +593,234 @@ impl<'columns, 'row> ItemColumnViewModel<'columns, 'row> {
         self.columns[column_index].kind == ItemColumnKind::FullText
     }
 
+    pub fn column_picks_location(&self, column_index: usize) -> bool {
+        self.columns[column_index].kind == ItemColumnKind::LocationChoice
+    }
+
+    pub fn add_location(&mut self, name: &str, num_bins: i64) -> AHResult<()> {
+        let checkpoint = begin_checkpoint(&mut self.store)?;
+        checkpoint.add(object!(
+            "type" => "location",
+            "name" => name,
+            "num_bins" => num_bins,
+        ))?;
+        commit(checkpoint, format!("add location {}", name))?;
+
+        Ok(())
+    }
+
+    /// The first row in the given location, matched by name or alias.
+    pub fn first_row_in_location(&self, name: &str) -> Option<usize> {
+        self.last_rendered_set.entries.values().position(|e| {
+            e.item.location.name.eq_ignore_ascii_case(name)
+                || e.item
+                    .location
+                    .alias()
+                    .is_some_and(|a| a.eq_ignore_ascii_case(name))
+        })
+    }
+
+    /// The first row in whichever shown location best fuzzily matches the pattern, along with that
+    /// location's name. A location named or aliased exactly by the pattern always wins.
+    pub fn first_row_in_matching_location(&self, pattern: &str) -> Option<(usize, String)> {
+        let entries = &self.last_rendered_set.entries;
+
+        if let Some(row) = self.first_row_in_location(pattern) {
+            return Some((row, entries[row].item.location.name.clone()));
+        }
+
+        let matcher = SkimMatcherV2::default().ignore_case();
+        let pattern = fold_for_search(pattern);
+        let mut best: Option<(i64, usize)> = None;
+        let mut seen_locations = HashSet::new();
+
+        for (row, entry) in entries.values().enumerate() {
+            let location = &entry.item.location;
+
+            // Only the first row of each location counts, so ties go to the earliest location.
+            if !seen_locations.insert(location.object_id) {
+                continue;
+            }
+
+            if let Some(score) = matcher.fuzzy_match(&fold_for_search(&location.name), &pattern) {
+                if best.is_none_or(|(best_score, _)| score > best_score) {
+                    best = Some((score, row));
+                }
+            }
+        }
+
+        best.map(|(_, row)| (row, entries[row].item.location.name.clone()))
+    }
+
+    pub fn locations(&self) -> AHResult<Vec<Location>> {
+        let mut locations = self
+            .store
+            .query(Q.equal("type", "location"))
+            .iter_as::<Location>()?
+            .collect::<Vec<_>>();
+        locations.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(locations)
+    }
+
+    /// Finds the next row after `from` (or before, if `reverse`) whose name contains the last
+    /// search, wrapping around at either end.
+    pub fn find_next_row(&self, from: usize, reverse: bool) -> Option<usize> {
+        let search = fold_for_search(self.last_search.as_ref()?);
+        let len = self.last_rendered_set.entries.len();
+
+        (1..=len)
+            .map(|offset| {
+                if reverse {
+                    (from + len - offset) % len
+                } else {
+                    (from + offset) % len
+                }
+            })
+            .find(|row| {
+                fold_for_search(&self.last_rendered_set.entries[*row].item.name).contains(&search)
+            })
+    }
+
+    pub fn sort_key(&self) -> ItemSortKey {
+        self.sort_key
+    }
+
+    pub fn cycle_sort_key(&mut self) {
+        self.sort_key = self.sort_key.next();
+    }
+
+    pub fn set_sort_key(&mut self, sort_key: ItemSortKey) {
+        self.sort_key = sort_key;
+    }
+
+    /// Whether searches look at every column, rather than just the location and name.
+    pub fn searches_all_columns(&self) -> bool {
+        self.search_all_columns
+    }
+
+    pub fn toggle_search_all_columns(&mut self) {
+        self.search_all_columns = !self.search_all_columns;
+    }
+
+    pub fn toggle_group_by_location(&mut self) {
+        self.group_by_location = !self.group_by_location;
+    }
+
+    /// A header for the first row of each location, with how many items it has, if grouping by
+    /// location. Items are only grouped when sorted by location and not being searched.
+    pub fn section_headers(&self) -> Vec<Option<String>> {
+        let rendered_set = &self.last_rendered_set;
+
+        if !self.group_by_location
+            || rendered_set.sort_key != ItemSortKey::LocationBinName
+            || rendered_set.search.as_ref().is_some_and(|s| !s.is_empty())
+        {
+            return vec![];
+        }
+
+        let location_ids: Vec<_> = rendered_set
+            .entries
+            .values()
+            .map(|e| e.item.location.object_id)
+            .collect();
+
+        rendered_set
+            .entries
+            .values()
+            .enumerate()
+            .map(|(row, e)| {
+                let location_id = e.item.location.object_id;
+                if row > 0 && location_ids[row - 1] == location_id {
+                    return None;
+                }
+
+                let count = location_ids[row..]
+                    .iter()
+                    .take_while(|id| **id == location_id)
+                    .count();
+
+                Some(format!("── {} ({}) ──", e.item.location.name, count))
+            })
+            .collect()
+    }
+
+    pub fn rendered_len(&self) -> usize {
+        self.last_rendered_set.entries.len()
+    }
+
+    /// How many items there are in total, whether or not they match the current search.
+    pub fn total_len(&self) -> usize {
+        self.last_fetched_items.len()
+    }
+
+    pub fn get_object_id(&self, row: usize) -> Option<i64> {
+        self.last_rendered_set
+            .entries
+            .get_index(row)
+            .map(|(object_id, _)| *object_id)
+    }
+
+    pub fn get_row(&self, object_id: i64) -> Option<usize> {
+        self.last_rendered_set.entries.get_index_of(&object_id)
+    }
+
+    pub fn get_item(&self, row: usize) -> Option<&Item> {
+        self.last_rendered_set
+            .entries
+            .get_index(row)
+            .map(|(_, e)| &e.item)
+    }
+
+    /// Moves the item to a new location, choosing a bin for it there.
+    pub fn set_location(&mut self, row: usize, location: &Location) -> AHResult<()> {
+        let bin_no = choose_bin(&self.store, location, FillOrder::default())?;
+
+        let (object_id, _) = self.last_rendered_set.edit_item(row, |item| {
+            item.location = location.clone();
+            item.rest.remove("location_id");
+            item.bin_no = bin_no;
+        });
+
+        self.edited_items.insert(object_id);
+
+        Ok(())
+    }
+
     pub fn get_column_len(&self, row_index: usize, column_index: usize) -> Option<usize> {
         if !self.column_allows_char_selection(column_index) {
             return None;
         }
 
         let (_, ItemRenderEntry { column_widths, .. }) =
-            self.last_rendered_set.entries.get_index(row_index).unwrap();
+            self.last_rendered_set.entries.get_index(row_index)?;
 
         Some(column_widths[column_index])
     }
 
-    pub fn insert_item(&mut self, after_index: usize, search: &Option<String>) -> AHResult<()> {
-        let (after_object_id, _) = self
-            .last_rendered_set
-            .entries
-            .get_index(after_index)
-            .unwrap();
-        let after_item: Item = self
-            .store
-            .query(Item::q().id(*after_object_id))
-            .one_converted(&self.store)
-            .unwrap();
-        let last_location = after_item.location.clone();
+    /// Adds a new item in the same location as the given row, just after it (or before, if
+    /// `before`). With no rows to go by, the item goes in the first location.
+    pub fn insert_item(
+        &mut self,
+        index: usize,
+        before: bool,
+        search: &Option<String>,
+    ) -> AHResult<()> {
+        let last_location = match self.last_rendered_set.entries.get_index(index) {
+            Some((sibling_object_id, _)) => {
+                let sibling_item: Item = self
+                    .store
+                    .query(Item::q().id(*sibling_object_id))
+                    .one_converted(&self.store)
+                    .unwrap();
+
+                sibling_item.location
+            }
+            None => match self.locations()?.into_iter().next() {
+                Some(location) => location,
+                None => bail!("add a location before adding items"),
+            },
+        };
 
         let item_name = item_name_from_search(search);
 
@@ -447,88 +830,234 @@ impl<'columns, 'row> ItemColumnViewModel<'columns, 'row> {
             &last_location,
             None,
             ItemSize::M,
+            1,
+            CapacityCheck::Ignore,
         )?;
 
-        self.last_rendered_set.add_item(after_index, &item);
+        let position = if before { index } else { index + 1 };
+        self.last_rendered_set
+            .add_item(position.min(self.last_rendered_set.entries.len()), &item);
 
         Ok(())
     }
 
+    /// Adds a copy of the item in the same bin, right after it.
+    pub fn duplicate_item(&mut self, row_index: usize) -> AHResult<String> {
+        let (_, ItemRenderEntry { item, .. }) =
+            self.last_rendered_set.entries.get_index(row_index).unwrap();
+        let item = item.clone();
+
+        let copy = add_item(
+            &mut self.store,
+            format!("{} (copy)", item.name),
+            &item.location,
+            Some(item.bin_no),
+            item.size.parse::<ItemSize>()?,
+            item.count(),
+            CapacityCheck::Ignore,
+        )?;
+
+        self.last_rendered_set.add_item(row_index + 1, &copy);
+
+        Ok(copy.name)
+    }
+
     pub fn delete_item(&mut self, row_index: usize) -> AHResult<String> {
         let (object_id, ItemRenderEntry { item, .. }) =
             self.last_rendered_set.entries.get_index(row_index).unwrap();
 
-        let checkpoint = self.store.checkpoint()?;
+        let checkpoint = begin_checkpoint(&mut self.store)?;
         checkpoint.query(Item::q().id(*object_id)).delete()?;
-        checkpoint.commit(format!("delete item: {}", item.name))?;
+        commit(checkpoint, format!("delete item: {}", item.name))?;
 
         Ok(item.name.clone())
     }
 
-    pub fn insert_char(&mut self, row: usize, cell: usize, i: usize, c: char) -> usize {
+    /// Deletes the items in all the given rows as one change, returning how many there were.
+    pub fn delete_items(&mut self, row_indices: &[usize]) -> AHResult<usize> {
+        let object_ids: Vec<_> = row_indices
+            .iter()
+            .filter_map(|row| self.get_object_id(*row))
+            .collect();
+
+        let checkpoint = begin_checkpoint(&mut self.store)?;
+        for object_id in &object_ids {
+            checkpoint.query(Item::q().id(*object_id)).delete()?;
+        }
+        commit(checkpoint, format!("delete {} items", object_ids.len()))?;
+
+        Ok(object_ids.len())
+    }
+
+    /// Moves the items in all the given rows to a new location as one change, spreading them
+    /// across its emptiest bins. Any unsaved edits are saved first.
+    pub fn move_items(&mut self, row_indices: &[usize], location: &Location) -> AHResult<usize> {
+        let items: Vec<_> = row_indices
+            .iter()
+            .filter_map(|row| self.get_item(*row).cloned())
+            .collect();
+
+        self.persist_pending_edits()?;
+
+        let location_id = location.object_id.unwrap();
+        let weights = size_weights(&self.store)?;
+        let mut fullnesses = bin_fullnesses(&self.store, location_id, location.num_bins)?;
+
+        let checkpoint = begin_checkpoint(&mut self.store)?;
+        for item in &items {
+            // Items already in the location shouldn't count against the bins they're leaving.
+            if item.location.object_id == location.object_id {
+                if let Some(fullness) = fullnesses.get_mut(&item.bin_no) {
+                    *fullness -= item_fullness(&weights, item)?;
+                }
+            }
+
+            let bin_no = emptiest_bin(&fullnesses, location.num_bins);
+            if let Some(fullness) = fullnesses.get_mut(&bin_no) {
+                *fullness += item_fullness(&weights, item)?;
+            }
+
+            checkpoint
+                .query(Item::q().id(item.object_id.unwrap()))
+                .set(object!(
+                    "location_id" => location_id,
+                    "bin_no" => bin_no,
+                ))?;
+        }
+        commit(
+            checkpoint,
+            format!("move {} items to {}", items.len(), location.name),
+        )?;
+
+        Ok(items.len())
+    }
+
+    pub fn insert_char(&mut self, row: usize, cell: usize, i: usize, c: char) -> AHResult<usize> {
         let column_insert_char = match self.columns[cell].insert_char {
-            Some(f) => f,
-            None => return i,
+            Some(f) if row < self.rendered_len() => f,
+            _ => return Ok(i),
         };
 
-        let (object_id, new_cursor) = self
-            .last_rendered_set
-            .edit_item(row, |item| column_insert_char(item, i, c));
+        let (object_id, new_cursor) =
+            self.edit_item_with_history(row, |item| column_insert_char(item, i, c));
 
-        self.edited_items.insert(object_id);
+        if new_cursor.is_ok() {
+            self.edited_items.insert(object_id);
+        }
 
         new_cursor
     }
 
     pub fn delete_char(&mut self, row: usize, cell: usize, i: usize) {
         let column_delete_char = match self.columns[cell].delete_char {
-            Some(f) => f,
-            None => return,
+            Some(f) if row < self.rendered_len() => f,
+            _ => return,
         };
 
-        let (object_id, _) = self
-            .last_rendered_set
-            .edit_item(row, |item| column_delete_char(item, i));
+        let (object_id, _) = self.edit_item_with_history(row, |item| column_delete_char(item, i));
 
         self.edited_items.insert(object_id);
     }
 
+    fn edit_item_with_history<T>(
+        &mut self,
+        row: usize,
+        editor: impl FnOnce(&mut Item) -> T,
+    ) -> (i64, T) {
+        let (_, entry) = self.last_rendered_set.entries.get_index(row).unwrap();
+        let prior_item = entry.item.clone();
+
+        let (object_id, value) = self.last_rendered_set.edit_item(row, editor);
+
+        if self.last_rendered_set.entries[&object_id].item != prior_item {
+            let history = self.edit_histories.entry(object_id).or_default();
+            history.undo.push(prior_item);
+            history.redo.clear();
+        }
+
+        (object_id, value)
+    }
+
+    fn restore_edit(&mut self, row: usize, redo: bool) -> bool {
+        let object_id = match self.get_object_id(row) {
+            Some(object_id) => object_id,
+            None => return false,
+        };
+        let history = match self.edit_histories.get_mut(&object_id) {
+            Some(history) => history,
+            None => return false,
+        };
+        let (from, to) = if redo {
+            (&mut history.redo, &mut history.undo)
+        } else {
+            (&mut history.undo, &mut history.redo)
+        };
+
+        let restored_item = match from.pop() {
+            Some(item) => item,
+            None => return false,
+        };
+        to.push(self.last_rendered_set.entries[&object_id].item.clone());
+
+        self.last_rendered_set
+            .edit_item(row, |item| *item = restored_item);
+
+        true
+    }
+
+    /// Reverts the last unsaved keystroke made to the item, returning whether there was one.
+    pub fn undo_edit(&mut self, row: usize) -> bool {
+        self.restore_edit(row, false)
+    }
+
+    /// Reapplies the last keystroke reverted by `undo_edit`, returning whether there was one.
+    pub fn redo_edit(&mut self, row: usize) -> bool {
+        self.restore_edit(row, true)
+    }
+
+    pub fn has_pending_edits(&self) -> bool {
+        !self.edited_items.is_empty()
+    }
+
     pub fn persist_pending_edits(&mut self) -> AHResult<usize> {
-        if self.edited_items.len() == 0 {
+        if self.edited_items.is_empty() {
             return Ok(0);
         }
 
         for object_id in self.edited_items.iter() {
             let edited_item = self.last_rendered_set.entries[object_id].item.clone();
             let edited_item_name = edited_item.name.clone();
-            let checkpoint = self.store.checkpoint()?;
+            let checkpoint = begin_checkpoint(&mut self.store)?;
             checkpoint
                 .query(Item::q().id(*object_id))
                 .set(edited_item.into())?;
-            checkpoint.commit(format!("update item: {}", edited_item_name))?;
+            commit(checkpoint, format!("update item: {}", edited_item_name))?;
         }
 
         let updated = self.edited_items.len();
         self.edited_items.clear();
+        self.edit_histories.clear();
 
         Ok(updated)
     }
 
     pub fn persist_current_pending_edit(&mut self, row: usize) -> AHResult<Option<String>> {
-        if self.edited_items.len() == 0 {
+        if self.edited_items.is_empty() {
             return Ok(None);
         }
 
         let (object_id, entry) = self.last_rendered_set.entries.get_index(row).unwrap();
 
-        if let Some(_) = self.edited_items.take(object_id) {
+        if self.edited_items.take(object_id).is_some() {
+            self.edit_histories.remove(object_id);
+
             let edited_item = entry.item.clone();
             let edited_item_name = edited_item.name.clone();
-            let checkpoint = self.store.checkpoint()?;
+            let checkpoint = begin_checkpoint(&mut self.store)?;
             checkpoint
                 .query(Item::q().id(*object_id))
                 .set(edited_item.into())?;
-            checkpoint.commit(format!("update item: {}", edited_item_name))?;
+            commit(checkpoint, format!("update item: {}", edited_item_name))?;
 
             Ok(Some(edited_item_name))
         } else {
@@ -539,7 +1068,17 @@ impl<'columns, 'row> ItemColumnViewModel<'columns, 'row> {
     pub fn undo(&mut self) -> AHResult<Option<String>> {
         self.persist_pending_edits()?;
 
-        let description = self.store.undo()?;
+        let description = self.redo_stack.undo(&mut self.store)?;
+
+        self.last_updated_checkpoint = 0;
+
+        Ok(description)
+    }
+
+    pub fn redo(&mut self) -> AHResult<Option<String>> {
+        self.persist_pending_edits()?;
+
+        let description = self.redo_stack.redo(&mut self.store)?;
 
         self.last_updated_checkpoint = 0;
 
@@ -551,6 +1090,225 @@ impl<'columns, 'row> ItemColumnViewModel<'columns, 'row> {
 mod tests {
     use super::*;
 
+    use qualia::{object, Object};
+    use tempfile::{Builder, TempDir};
+
+    lazy_static::lazy_static! {
+        static ref TEST_COLUMNS: Vec<ItemColumn> = vec![ItemColumn {
+            header: "Name".to_string(),
+            width: ItemColumnWidth::Expand,
+            kind: ItemColumnKind::FullText,
+            display: |i| Ok(i.name.clone()),
+            insert_char: Some(|item, i, c| {
+                item.name.insert(i, c);
+                Ok(i + 1)
+            }),
+            delete_char: Some(|item, i| {
+                item.name.remove(i);
+            }),
+            searchable: true,
+        }];
+    }
+
+    fn open_test_view_model(
+        items: &[(&str, &str)],
+    ) -> (TempDir, ItemColumnViewModel<'static, 'static>) {
+        let temp_dir = Builder::new().prefix("pachinko-editor").tempdir().unwrap();
+        let mut store = Store::open(temp_dir.path().join("pachinko-test-store.qualia")).unwrap();
+
+        let checkpoint = store.checkpoint().unwrap();
+        let location_id = checkpoint
+            .add(object!("type" => "location", "name" => "Test", "num_bins" => 1))
+            .unwrap();
+        for (name, size) in items {
+            checkpoint
+                .add(object!(
+                    "type" => "item",
+                    "name" => *name,
+                    "location_id" => location_id,
+                    "bin_no" => 1,
+                    "size" => *size,
+                ))
+                .unwrap();
+        }
+        checkpoint.commit("add test items").unwrap();
+
+        let mut view_model = ItemColumnViewModel::new(store, &TEST_COLUMNS, Color::Reset);
+        view_model.render(&None).unwrap();
+
+        (temp_dir, view_model)
+    }
+
+    #[test]
+    fn find_next_row_wraps_around_in_both_directions() {
+        let (_temp_dir, mut view_model) = open_test_view_model(&[
+            ("Blue hat", "S"),
+            ("Red coat", "S"),
+            ("Red hat", "S"),
+            ("Scarf", "S"),
+        ]);
+
+        assert_eq!(view_model.find_next_row(0, false), None);
+
+        view_model.render(&Some("hat".to_string())).unwrap();
+        view_model.render(&None).unwrap();
+
+        assert_eq!(view_model.find_next_row(0, false), Some(2));
+        assert_eq!(view_model.find_next_row(2, false), Some(0));
+        assert_eq!(view_model.find_next_row(0, true), Some(2));
+        assert_eq!(view_model.find_next_row(3, true), Some(2));
+    }
+
+    #[test]
+    fn undo_edit_and_redo_edit_step_through_unsaved_keystrokes() {
+        let (_temp_dir, mut view_model) = open_test_view_model(&[("Hat", "S")]);
+
+        view_model.insert_char(0, 0, 3, 's').unwrap();
+        view_model.delete_char(0, 0, 0);
+        assert_eq!(view_model.get_item(0).unwrap().name, "ats");
+
+        assert!(view_model.undo_edit(0));
+        assert_eq!(view_model.get_item(0).unwrap().name, "Hats");
+        assert!(view_model.undo_edit(0));
+        assert_eq!(view_model.get_item(0).unwrap().name, "Hat");
+        assert!(!view_model.undo_edit(0));
+
+        assert!(view_model.redo_edit(0));
+        assert_eq!(view_model.get_item(0).unwrap().name, "Hats");
+
+        view_model.persist_pending_edits().unwrap();
+        assert!(!view_model.undo_edit(0));
+        assert!(!view_model.redo_edit(0));
+    }
+
+    #[test]
+    fn deleting_several_items_makes_one_change() {
+        let (_temp_dir, mut view_model) =
+            open_test_view_model(&[("Anvil", "X"), ("Bell", "S"), ("Cup", "M")]);
+
+        assert_eq!(view_model.delete_items(&[0, 2]).unwrap(), 2);
+        view_model.update(&None).unwrap();
+        assert_eq!(view_model.rendered_len(), 1);
+        assert_eq!(view_model.get_item(0).unwrap().name, "Bell");
+
+        view_model.undo().unwrap();
+        view_model.update(&None).unwrap();
+        assert_eq!(view_model.rendered_len(), 3);
+    }
+
+    #[test]
+    fn moving_several_items_spreads_them_across_bins() {
+        let (_temp_dir, mut view_model) =
+            open_test_view_model(&[("Anvil", "X"), ("Bell", "S"), ("Cup", "M")]);
+        view_model.add_location("Shelf", 2).unwrap();
+        let shelf = view_model
+            .locations()
+            .unwrap()
+            .into_iter()
+            .find(|l| l.name == "Shelf")
+            .unwrap();
+
+        assert_eq!(view_model.move_items(&[0, 1], &shelf).unwrap(), 2);
+        view_model.update(&None).unwrap();
+
+        let moved: Vec<_> = (0..view_model.rendered_len())
+            .map(|row| view_model.get_item(row).unwrap())
+            .map(|item| (item.name.as_str(), item.location.name.as_str(), item.bin_no))
+            .collect();
+        assert_eq!(
+            moved,
+            vec![
+                ("Anvil", "Shelf", 1),
+                ("Bell", "Shelf", 2),
+                ("Cup", "Test", 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn rejected_characters_do_not_count_as_edits() {
+        lazy_static::lazy_static! {
+            static ref PICKY_COLUMNS: Vec<ItemColumn> = vec![ItemColumn {
+                header: "Size".to_string(),
+                width: ItemColumnWidth::Shrink,
+                kind: ItemColumnKind::Choice,
+                display: |i| Ok(i.size.clone()),
+                insert_char: Some(|_, _, _| bail!("Size must be S/M/L/X")),
+                delete_char: None,
+                searchable: false,
+            }];
+        }
+        let (_temp_dir, view_model) = open_test_view_model(&[("Hat", "S")]);
+        let mut view_model =
+            ItemColumnViewModel::new(view_model.store, &PICKY_COLUMNS, Color::Reset);
+        view_model.update(&None).unwrap();
+
+        assert_eq!(
+            view_model
+                .insert_char(0, 0, 0, 'q')
+                .unwrap_err()
+                .to_string(),
+            "Size must be S/M/L/X"
+        );
+        assert!(!view_model.has_pending_edits());
+        assert!(!view_model.undo_edit(0));
+    }
+
+    #[test]
+    fn grouping_by_location_adds_a_header_for_each_location() {
+        let (_temp_dir, mut view_model) =
+            open_test_view_model(&[("Anvil", "X"), ("Bell", "S"), ("Cup", "M")]);
+        view_model.add_location("Shelf", 1).unwrap();
+        let shelf = view_model
+            .locations()
+            .unwrap()
+            .into_iter()
+            .find(|l| l.name == "Shelf")
+            .unwrap();
+        view_model.move_items(&[2], &shelf).unwrap();
+        view_model.update(&None).unwrap();
+
+        assert!(view_model.section_headers().is_empty());
+
+        view_model.toggle_group_by_location();
+        assert_eq!(
+            view_model.section_headers(),
+            vec![
+                Some("── Test (2) ──".to_string()),
+                None,
+                Some("── Shelf (1) ──".to_string()),
+            ]
+        );
+
+        view_model.update(&Some("bell".to_string())).unwrap();
+        assert!(view_model.section_headers().is_empty());
+    }
+
+    #[test]
+    fn jumping_to_a_location_finds_its_first_row() {
+        let (_temp_dir, mut view_model) =
+            open_test_view_model(&[("Anvil", "X"), ("Bell", "S"), ("Cup", "M")]);
+        view_model.add_location("Garden shed", 1).unwrap();
+        let shed = view_model
+            .locations()
+            .unwrap()
+            .into_iter()
+            .find(|l| l.name == "Garden shed")
+            .unwrap();
+        view_model.move_items(&[1, 2], &shed).unwrap();
+        view_model.update(&None).unwrap();
+
+        assert_eq!(
+            view_model.first_row_in_matching_location("gshd"),
+            Some((1, "Garden shed".to_string()))
+        );
+        assert_eq!(
+            view_model.first_row_in_matching_location("test"),
+            Some((0, "Test".to_string()))
+        );
+        assert_eq!(view_model.first_row_in_matching_location("xyz"), None);
+    }
+
     #[test]
     fn item_name_returns_empty_for_none() {
         assert_eq!(item_name_from_search(&None), "".to_string());
@@ -567,4 +1325,84 @@ mod tests {
             "Abc Def".to_string()
         );
     }
+
+    #[test]
+    fn changing_the_sort_key_reorders_rows() {
+        let (_temp_dir, mut view_model) =
+            open_test_view_model(&[("Anvil", "X"), ("Bell", "S"), ("Cup", "M")]);
+        let names = |view_model: &ItemColumnViewModel| {
+            (0..view_model.rendered_len())
+                .map(|row| view_model.get_item(row).unwrap().name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(&view_model), vec!["Anvil", "Bell", "Cup"]);
+
+        view_model.cycle_sort_key();
+        view_model.cycle_sort_key();
+        view_model.update(&None).unwrap();
+        assert_eq!(view_model.sort_key(), ItemSortKey::Size);
+        assert_eq!(names(&view_model), vec!["Bell", "Cup", "Anvil"]);
+    }
+
+    #[test]
+    fn inserting_an_item_before_a_row_takes_its_place() {
+        let (_temp_dir, mut view_model) = open_test_view_model(&[("Anvil", "X"), ("Bell", "S")]);
+
+        view_model
+            .insert_item(1, true, &Some("cup".to_string()))
+            .unwrap();
+        view_model.insert_item(0, false, &None).unwrap();
+
+        assert_eq!(
+            (0..view_model.rendered_len())
+                .map(|row| view_model.get_item(row).unwrap().name.clone())
+                .collect::<Vec<_>>(),
+            vec!["Anvil", "", "Cup", "Bell"]
+        );
+    }
+
+    #[test]
+    fn inserting_into_an_empty_list_uses_the_first_location() {
+        let (_temp_dir, mut view_model) = open_test_view_model(&[]);
+
+        view_model.insert_char(0, 0, 0, 'x').unwrap();
+        view_model.insert_item(0, false, &None).unwrap();
+
+        assert_eq!(view_model.rendered_len(), 1);
+        assert_eq!(view_model.get_item(0).unwrap().location.name, "Test");
+    }
+
+    #[test]
+    fn sort_keys_should_parse_from_their_names() {
+        for sort_key in [
+            ItemSortKey::LocationBinName,
+            ItemSortKey::Name,
+            ItemSortKey::Size,
+        ] {
+            assert_eq!(
+                sort_key.to_string().parse::<ItemSortKey>().unwrap(),
+                sort_key
+            );
+        }
+        assert!("bin".parse::<ItemSortKey>().is_err());
+    }
+
+    #[test]
+    fn fold_for_search_strips_case_and_accents() {
+        assert_eq!(fold_for_search("Café Crème"), "cafe creme");
+        assert_eq!(
+            fold_for_search("Zoë").chars().count(),
+            "Zoë".chars().count()
+        );
+    }
+
+    #[test]
+    fn searching_ignores_accents() {
+        let (_temp_dir, mut view_model) = open_test_view_model(&[("Café", "S"), ("Cup", "M")]);
+
+        view_model.update(&Some("cafe".to_string())).unwrap();
+        assert_eq!(view_model.rendered_len(), 1);
+        assert_eq!(view_model.get_item(0).unwrap().name, "Café");
+    }
 }
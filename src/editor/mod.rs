@@ -1,6 +1,7 @@
 mod app;
 mod item;
 mod sheet;
+mod theme;
 
 use crossterm::{
     event::{
@@ -8,7 +9,10 @@ use crossterm::{
         PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
     },
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{
+        disable_raw_mode, enable_raw_mode, is_raw_mode_enabled, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
 };
 use lazy_static::lazy_static;
 use std::sync::{
@@ -21,13 +25,38 @@ use tui::{backend::CrosstermBackend, Terminal};
 use crate::{AHResult, CommonOpts};
 
 static CTRLC_INSTALLED: AtomicBool = AtomicBool::new(false);
+static PANIC_HOOK_INSTALLED: AtomicBool = AtomicBool::new(false);
 
 lazy_static! {
     static ref RUNNING: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
 }
 
+fn restore_terminal() -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(
+        io::stdout(),
+        PopKeyboardEnhancementFlags,
+        DisableMouseCapture,
+        LeaveAlternateScreen,
+    )
+}
+
 pub(crate) fn run_editor(opts: CommonOpts) -> AHResult<()> {
     let store = opts.open_store().unwrap();
+    let theme = theme::Theme::load(opts.theme_path()?)?;
+
+    // Otherwise, a panic would leave the terminal in raw mode, with the message lost on the
+    // alternate screen.
+    if !PANIC_HOOK_INSTALLED.swap(true, Ordering::SeqCst) {
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if is_raw_mode_enabled().unwrap_or(false) {
+                let _ = restore_terminal();
+            }
+
+            default_hook(info);
+        }));
+    }
 
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -45,10 +74,9 @@ pub(crate) fn run_editor(opts: CommonOpts) -> AHResult<()> {
     let mut terminal = Terminal::new(backend)?;
 
     RUNNING.store(true, Ordering::SeqCst);
-    if CTRLC_INSTALLED
+    if !CTRLC_INSTALLED
         .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
-        .map_or_else(|e| e, |a| a)
-        == false
+        .unwrap_or_else(|e| e)
     {
         let running = RUNNING.clone();
         ctrlc::set_handler(move || {
@@ -56,7 +84,12 @@ pub(crate) fn run_editor(opts: CommonOpts) -> AHResult<()> {
         })?;
     }
 
-    let mut app = app::App::new(store, RUNNING.clone());
+    let mut app = app::App::new(
+        store,
+        RUNNING.clone(),
+        theme,
+        app::EditorPosition::load(opts.editor_position_path()?)?,
+    );
 
     while RUNNING.load(Ordering::SeqCst) {
         terminal.draw(|f| app.render_to(f))?;
@@ -74,14 +107,10 @@ pub(crate) fn run_editor(opts: CommonOpts) -> AHResult<()> {
         }
     }
 
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        PopKeyboardEnhancementFlags,
-        DisableMouseCapture,
-        LeaveAlternateScreen,
-    )?;
+    restore_terminal()?;
     terminal.show_cursor()?;
 
+    app.position().save(opts.editor_position_path()?)?;
+
     Ok(())
 }
@@ -22,6 +22,8 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+use std::collections::BTreeSet;
+
 use tui::{
     buffer::Buffer,
     layout::{Constraint, Direction, Layout, Rect},
@@ -135,6 +137,7 @@ impl<'a> Row<'a> {
 
     /// Set the fixed height of the [`Row`]. Any [`Cell`] whose content has more lines than this
     /// height will see its content truncated.
+    #[allow(dead_code)]
     pub fn height(mut self, height: u16) -> Self {
         self.height = height;
         self
@@ -148,6 +151,7 @@ impl<'a> Row<'a> {
     }
 
     /// Set the bottom margin. By default, the bottom margin is `0`.
+    #[allow(dead_code)]
     pub fn bottom_margin(mut self, margin: u16) -> Self {
         self.bottom_margin = margin;
         self
@@ -228,6 +232,14 @@ pub struct Sheet<'a> {
     highlight_i_style: Style,
     /// Symbol in front of the selected rom
     highlight_symbol: Option<&'a str>,
+    /// Symbol in front of marked rows, and which rows are marked
+    mark_symbol: Option<&'a str>,
+    marked_rows: Vec<bool>,
+    /// Style used to render rows marked in the `SheetState`
+    marked_row_style: Style,
+    /// Lines drawn above rows to start a new section, which can't be selected
+    section_headers: Vec<Option<String>>,
+    section_header_style: Style,
     /// Optional header
     header: Option<Row<'a>>,
     /// Data to display in each row
@@ -248,11 +260,17 @@ impl<'a> Sheet<'a> {
             highlight_cell_style: Style::default(),
             highlight_i_style: Style::default(),
             highlight_symbol: None,
+            mark_symbol: None,
+            marked_rows: Vec::new(),
+            marked_row_style: Style::default(),
+            section_headers: Vec::new(),
+            section_header_style: Style::default(),
             header: None,
             rows: rows.into_iter().collect(),
         }
     }
 
+    #[allow(dead_code)]
     pub fn block(mut self, block: Block<'a>) -> Self {
         self.block = Some(block);
         self
@@ -276,16 +294,49 @@ impl<'a> Sheet<'a> {
         self
     }
 
+    #[allow(dead_code)]
     pub fn style(mut self, style: Style) -> Self {
         self.style = style;
         self
     }
 
+    #[allow(dead_code)]
     pub fn highlight_symbol(mut self, highlight_symbol: &'a str) -> Self {
         self.highlight_symbol = Some(highlight_symbol);
         self
     }
 
+    /// Shows the symbol in front of each row whose entry in `marked_rows` is true.
+    pub fn mark_rows(mut self, mark_symbol: &'a str, marked_rows: Vec<bool>) -> Self {
+        self.mark_symbol = Some(mark_symbol);
+        self.marked_rows = marked_rows;
+        self
+    }
+
+    pub fn marked_row_style(mut self, marked_row_style: Style) -> Self {
+        self.marked_row_style = marked_row_style;
+        self
+    }
+
+    /// Draws each row's entry in `section_headers`, if any, on its own line above it.
+    pub fn section_headers(mut self, section_headers: Vec<Option<String>>, style: Style) -> Self {
+        self.section_headers = section_headers;
+        self.section_header_style = style;
+        self
+    }
+
+    fn section_header_height(&self, i: usize) -> u16 {
+        match self.section_headers.get(i) {
+            Some(Some(_)) => 1,
+            _ => 0,
+        }
+    }
+
+    /// The height of the row along with its section header, if it has one.
+    fn row_total_height(&self, i: usize) -> u16 {
+        self.rows[i].total_height() + self.section_header_height(i)
+    }
+
     pub fn highlight_style(mut self, highlight_style: Style) -> Self {
         self.highlight_style = highlight_style;
         self
@@ -306,12 +357,20 @@ impl<'a> Sheet<'a> {
         self
     }
 
+    /// The width of the column before the first, for the highlight and mark symbols.
+    fn leading_symbol_width(&self) -> u16 {
+        [self.highlight_symbol, self.mark_symbol]
+            .iter()
+            .flatten()
+            .map(|s| s.width() as u16)
+            .max()
+            .unwrap_or(0)
+    }
+
     fn get_columns_widths(&self, max_width: u16, has_selection: bool) -> Vec<u16> {
         let mut constraints = Vec::with_capacity(self.widths.len() * 2 + 1);
         if has_selection {
-            let highlight_symbol_width =
-                self.highlight_symbol.map(|s| s.width() as u16).unwrap_or(0);
-            constraints.push(Constraint::Length(highlight_symbol_width));
+            constraints.push(Constraint::Length(self.leading_symbol_width()));
         }
         for constraint in self.widths {
             constraints.push(*constraint);
@@ -351,37 +410,38 @@ impl<'a> Sheet<'a> {
         let mut start = offset;
         let mut end = offset;
         let mut height = 0;
-        for item in self.rows.iter().skip(offset) {
-            if height + item.height > max_height {
+        for (i, item) in self.rows.iter().enumerate().skip(offset) {
+            if height + item.height + self.section_header_height(i) > max_height {
                 break;
             }
-            height += item.total_height();
+            height += self.row_total_height(i);
             end += 1;
         }
 
         let selected = selected.unwrap_or(offset).min(self.rows.len() - 1);
         while selected >= end {
-            height = height.saturating_add(self.rows[end].total_height());
+            height = height.saturating_add(self.row_total_height(end));
             end += 1;
             while height > max_height {
-                height = height.saturating_sub(self.rows[start].total_height());
+                height = height.saturating_sub(self.row_total_height(start));
                 start += 1;
             }
         }
         while selected < start {
             start -= 1;
-            height = height.saturating_add(self.rows[start].total_height());
+            height = height.saturating_add(self.row_total_height(start));
             while height > max_height {
                 end -= 1;
-                height = height.saturating_sub(self.rows[end].total_height());
+                height = height.saturating_sub(self.row_total_height(end));
             }
         }
         (start, end)
     }
 }
 
-#[derive(Copy, Debug, Clone)]
+#[derive(Copy, Debug, Clone, Default)]
 pub enum SheetSelection {
+    #[default]
     None,
     Row(usize),
     Cell(usize, usize),
@@ -390,17 +450,11 @@ pub enum SheetSelection {
 
 impl SheetSelection {
     pub fn is_none(&self) -> bool {
-        match *self {
-            Self::None => true,
-            _ => false,
-        }
+        matches!(*self, Self::None)
     }
 
     pub fn is_some(&self) -> bool {
-        match *self {
-            Self::None => false,
-            _ => true,
-        }
+        !self.is_none()
     }
 
     pub fn row(&self) -> Option<usize> {
@@ -452,17 +506,16 @@ impl SheetSelection {
     }
 }
 
-impl Default for SheetSelection {
-    fn default() -> Self {
-        Self::None
-    }
-}
-
 #[derive(Debug, Clone, Default)]
 pub struct SheetState {
     offset: usize,
     selection: SheetSelection,
+    // Rows marked for acting on together, apart from the selection.
+    marked: BTreeSet<usize>,
     last_rows_height: Option<u16>,
+    // Where each row and column was last drawn, for finding what was clicked on.
+    last_row_areas: Vec<(usize, Rect)>,
+    last_column_bounds: Vec<(u16, u16)>,
 }
 
 impl SheetState {
@@ -481,10 +534,33 @@ impl SheetState {
         self.select(f(self.selection));
     }
 
+    /// The marked rows, in order.
+    pub fn marked_rows(&self) -> Vec<usize> {
+        self.marked.iter().copied().collect()
+    }
+
+    pub fn set_marked_rows(&mut self, rows: impl IntoIterator<Item = usize>) {
+        self.marked = rows.into_iter().collect();
+    }
+
+    pub fn toggle_mark(&mut self, row: usize) {
+        if !self.marked.remove(&row) {
+            self.marked.insert(row);
+        }
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+    }
+
     pub fn get_offset(&self) -> usize {
         self.offset
     }
 
+    pub fn set_offset(&mut self, offset: usize) {
+        self.offset = offset;
+    }
+
     pub fn scroll_up(&mut self, delta: usize) {
         self.offset = self.offset.saturating_sub(delta);
 
@@ -499,6 +575,52 @@ impl SheetState {
         self.offset += delta;
         self.selection = self.selection.map_row(|r| r.max(self.offset));
     }
+
+    /// Scrolls down by a page, keeping `context` rows of the last page in view and selecting the
+    /// first row after them.
+    pub fn page_down(&mut self, context: usize) {
+        if let Some(last_rows_height) = self.last_rows_height {
+            let context = context.min((last_rows_height as usize).saturating_sub(1));
+
+            self.offset += (last_rows_height as usize - context).max(1);
+            let selected = self.offset + context;
+            self.selection = self.selection.map_row(|_| selected);
+        }
+    }
+
+    /// Scrolls up by a page, keeping `context` rows of the last page in view and selecting the
+    /// last row before them. At the top, this just selects the first row.
+    pub fn page_up(&mut self, context: usize) {
+        if let Some(last_rows_height) = self.last_rows_height {
+            let context = context.min((last_rows_height as usize).saturating_sub(1));
+
+            let selected = if self.offset == 0 {
+                0
+            } else {
+                self.offset = self
+                    .offset
+                    .saturating_sub((last_rows_height as usize - context).max(1));
+                self.offset + last_rows_height as usize - context - 1
+            };
+            self.selection = self.selection.map_row(|_| selected);
+        }
+    }
+
+    /// The row and column drawn at the given screen position, along with how far into the column
+    /// it is.
+    pub fn cell_at(&self, x: u16, y: u16) -> Option<(usize, usize, usize)> {
+        let (row, _) = self
+            .last_row_areas
+            .iter()
+            .find(|(_, area)| (area.top()..area.bottom()).contains(&y))?;
+        let (column, (left, _)) = self
+            .last_column_bounds
+            .iter()
+            .enumerate()
+            .find(|(_, (left, width))| (*left..left + width).contains(&x))?;
+
+        Some((*row, column, (x - left) as usize))
+    }
 }
 
 impl<'a> StatefulWidget for Sheet<'a> {
@@ -518,10 +640,12 @@ impl<'a> StatefulWidget for Sheet<'a> {
             None => area,
         };
 
-        let has_selection = state.selection.is_some();
+        let has_selection = state.selection.is_some() || self.mark_symbol.is_some();
         let columns_widths = self.get_columns_widths(table_area.width, has_selection);
         let highlight_symbol = self.highlight_symbol.unwrap_or("");
-        let blank_symbol = " ".repeat(highlight_symbol.width());
+        let mark_symbol = self.mark_symbol.unwrap_or("");
+        let leading_symbol_width = self.leading_symbol_width();
+        let blank_symbol = " ".repeat(leading_symbol_width as usize);
         let mut current_height = 0;
         let mut rows_height = table_area.height;
 
@@ -539,7 +663,7 @@ impl<'a> StatefulWidget for Sheet<'a> {
             );
             let mut col = table_area.left();
             if has_selection {
-                col += (highlight_symbol.width() as u16).min(table_area.width);
+                col += leading_symbol_width.min(table_area.width);
             }
             for (width, cell) in columns_widths.iter().zip(header.cells.iter()) {
                 render_cell(
@@ -575,6 +699,8 @@ impl<'a> StatefulWidget for Sheet<'a> {
         let (start, end) = self.get_row_bounds(state.selection.row(), state.offset, rows_height);
         state.last_rows_height = Some(rows_height);
         state.offset = start;
+        state.last_row_areas.clear();
+        state.last_column_bounds.clear();
         for (i, table_row) in self
             .rows
             .iter_mut()
@@ -582,6 +708,17 @@ impl<'a> StatefulWidget for Sheet<'a> {
             .skip(state.offset)
             .take(end - start)
         {
+            if let Some(Some(section_header)) = self.section_headers.get(i) {
+                buf.set_stringn(
+                    table_area.left(),
+                    table_area.top() + current_height,
+                    section_header,
+                    table_area.width as usize,
+                    self.section_header_style,
+                );
+                current_height += 1;
+            }
+
             let (row, col) = (table_area.top() + current_height, table_area.left());
             current_height += table_row.total_height();
             let table_row_area = Rect {
@@ -591,24 +728,39 @@ impl<'a> StatefulWidget for Sheet<'a> {
                 height: table_row.height,
             };
             buf.set_style(table_row_area, table_row.style);
+            state.last_row_areas.push((i, table_row_area));
             let is_selected = state.selection.row().map(|s| s == i).unwrap_or(false);
             let table_row_start_col = if has_selection {
-                let symbol = if is_selected {
+                let symbol = if is_selected && !highlight_symbol.is_empty() {
                     highlight_symbol
+                } else if self.marked_rows.get(i).copied().unwrap_or(false) {
+                    mark_symbol
                 } else {
                     &blank_symbol
                 };
-                let (col, _) =
-                    buf.set_stringn(col, row, symbol, table_area.width as usize, table_row.style);
-                col
+                buf.set_stringn(col, row, symbol, table_area.width as usize, table_row.style);
+                col + leading_symbol_width.min(table_area.width)
             } else {
                 col
             };
 
             let mut col = table_row_start_col;
+            if state.marked.contains(&i) {
+                buf.set_style(table_row_area, self.marked_row_style);
+            }
             if is_selected {
                 buf.set_style(table_row_area, self.highlight_style);
             }
+            if state.last_column_bounds.is_empty() {
+                state.last_column_bounds = columns_widths
+                    .iter()
+                    .scan(col, |left, width| {
+                        let bounds = (*left, *width);
+                        *left += *width + self.column_spacing;
+                        Some(bounds)
+                    })
+                    .collect();
+            }
             for (j, (width, cell)) in columns_widths
                 .iter()
                 .zip(table_row.cells.iter())
@@ -681,6 +833,19 @@ impl<'a> Widget for Sheet<'a> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn toggling_a_mark_twice_unmarks_the_row() {
+        let mut state = SheetState::default();
+
+        state.toggle_mark(3);
+        state.toggle_mark(1);
+        state.toggle_mark(3);
+        assert_eq!(state.marked_rows(), vec![1]);
+
+        state.clear_marks();
+        assert!(state.marked_rows().is_empty());
+    }
+
     #[test]
     #[should_panic]
     fn sheet_invalid_percentages() {
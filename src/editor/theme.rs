@@ -0,0 +1,139 @@
+use anyhow::{bail, Context};
+use std::path::Path;
+use tui::style::Color;
+
+use crate::AHResult;
+
+/// Colors used by the editor, which can be changed in a TOML file for terminals where the defaults
+/// are hard to read.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Theme {
+    /// Background of the selected row.
+    pub highlight: Color,
+    /// Background of the selected cell.
+    pub cell: Color,
+    /// Background of the character cursor.
+    pub cursor: Color,
+    /// Background of characters matching the search.
+    pub search_match: Color,
+    /// Background of rows marked for deleting or moving together.
+    pub marked: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            highlight: Color::Indexed(238),
+            cell: Color::Indexed(242),
+            cursor: Color::Indexed(242),
+            search_match: Color::Indexed(58),
+            marked: Color::Indexed(24),
+        }
+    }
+}
+
+/// Parses a color given as a 256-color palette index, a name like "blue" or a hex code like
+/// "#336699".
+fn parse_color(value: &toml::Value) -> AHResult<Color> {
+    if let Some(index) = value.as_integer() {
+        return Ok(Color::Indexed(
+            u8::try_from(index).context("color index must be between 0 and 255")?,
+        ));
+    }
+
+    let name = match value.as_str() {
+        Some(name) => name.to_ascii_lowercase(),
+        None => bail!("color must be a number or a string"),
+    };
+
+    if let Some(hex) = name.strip_prefix('#') {
+        if hex.len() != 6 {
+            bail!("hex color must have six digits: {}", name);
+        }
+
+        let rgb =
+            u32::from_str_radix(hex, 16).with_context(|| format!("invalid hex color: {}", name))?;
+
+        return Ok(Color::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8));
+    }
+
+    Ok(match name.as_str() {
+        "reset" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" => Color::Gray,
+        "darkgray" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => bail!("unknown color: {}", name),
+    })
+}
+
+impl Theme {
+    fn from_toml(contents: &str) -> AHResult<Self> {
+        let table: toml::value::Table = toml::from_str(contents)?;
+        let mut theme = Self::default();
+
+        for (key, value) in &table {
+            let color = parse_color(value).with_context(|| format!("invalid {} color", key))?;
+
+            match key.as_str() {
+                "highlight" => theme.highlight = color,
+                "cell" => theme.cell = color,
+                "cursor" => theme.cursor = color,
+                "match" => theme.search_match = color,
+                "marked" => theme.marked = color,
+                _ => bail!("unknown theme setting: {}", key),
+            }
+        }
+
+        Ok(theme)
+    }
+
+    /// Reads the theme from the given file, using the defaults if it doesn't exist.
+    pub fn load(path: impl AsRef<Path>) -> AHResult<Self> {
+        let path = path.as_ref();
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        Self::from_toml(&std::fs::read_to_string(path)?)
+            .with_context(|| format!("failed to read theme {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn themes_should_default_missing_colors() {
+        assert_eq!(
+            Theme::from_toml("highlight = 17\nmatch = \"yellow\"\ncursor = \"#ff8000\"").unwrap(),
+            Theme {
+                highlight: Color::Indexed(17),
+                cursor: Color::Rgb(255, 128, 0),
+                search_match: Color::Yellow,
+                ..Theme::default()
+            }
+        );
+    }
+
+    #[test]
+    fn themes_should_reject_unknown_settings_and_colors() {
+        assert!(Theme::from_toml("background = 1").is_err());
+        assert!(Theme::from_toml("cell = \"chartreuse\"").is_err());
+        assert!(Theme::from_toml("cell = 300").is_err());
+    }
+}
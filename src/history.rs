@@ -0,0 +1,277 @@
+use anyhow::Context;
+use qualia::{Object, PropValue, Store, Q};
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::utils::{begin_checkpoint, check_writable, commit, Failure};
+use crate::AHResult;
+
+/// The objects an undo changed, as they were before it, so that the undo can be reversed.
+struct UndoneCheckpoint {
+    description: String,
+    // Objects that the undo removed or changed.
+    restore: Vec<Object>,
+    // Objects that the undo brought back.
+    remove: Vec<i64>,
+}
+
+/// Undone checkpoints that can still be redone.
+///
+/// qualia forgets a checkpoint once it is undone, so this compares the store before and after each
+/// undo. Re-added objects get new IDs, which are tracked so that later redos and references from
+/// other objects (like `location_id`) still line up.
+#[derive(Default)]
+pub struct RedoStack {
+    // The checkpoint the store was at after the last undo or redo; any other change to the store
+    // means the undone checkpoints can no longer be redone.
+    checkpoint_id: Option<i64>,
+    undone: Vec<UndoneCheckpoint>,
+    remapped_ids: HashMap<i64, i64>,
+}
+
+fn _open_read_only(store_path: impl AsRef<Path>) -> AHResult<rusqlite::Connection> {
+    Ok(rusqlite::Connection::open_with_flags(
+        store_path,
+        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY,
+    )?)
+}
+
+/// Descriptions of the most recent checkpoints in the store at the given path, newest first.
+pub fn recent_checkpoint_descriptions(
+    store_path: impl AsRef<Path>,
+    limit: usize,
+) -> AHResult<Vec<String>> {
+    let conn = _open_read_only(store_path)?;
+
+    let descriptions = conn
+        .prepare(
+            "SELECT description
+                FROM checkpoints
+                ORDER BY checkpoint_id DESC
+                LIMIT ?",
+        )?
+        .query_map([limit as i64], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    Ok(descriptions)
+}
+
+/// When each checkpoint in the store at the given path was made (in UTC) and its description,
+/// oldest first.
+pub fn checkpoint_log(store_path: impl AsRef<Path>) -> AHResult<Vec<(String, String)>> {
+    let conn = _open_read_only(store_path)?;
+
+    let log = conn
+        .prepare(
+            "SELECT timestamp, description
+                FROM checkpoints
+                ORDER BY checkpoint_id",
+        )?
+        .query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+            ))
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+
+    Ok(log)
+}
+
+/// Rebuilds the store at the given path to drop the space left behind by deleted objects and undone
+/// checkpoints. `while_locked` is run once nothing else can open the store.
+///
+/// Fails without changing anything if the store is open elsewhere, like in the editor.
+pub fn compact_store(
+    store_path: impl AsRef<Path>,
+    while_locked: impl FnOnce() -> AHResult<()>,
+) -> AHResult<()> {
+    check_writable()?;
+
+    let conn = rusqlite::Connection::open(store_path)?;
+    conn.busy_timeout(std::time::Duration::ZERO)?;
+    conn.pragma_update(None, "locking_mode", "EXCLUSIVE")?;
+    conn.execute_batch("BEGIN EXCLUSIVE; COMMIT;")
+        .context(Failure::Store(
+            "store is in use; close any editors or consoles using it and try again".to_string(),
+        ))?;
+
+    while_locked()?;
+
+    conn.execute_batch("VACUUM")?;
+    conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+
+    Ok(())
+}
+
+fn _last_checkpoint_id(store: &Store) -> Option<i64> {
+    store.last_checkpoint_id().ok()
+}
+
+/// Whether the property holds the ID of another object, like `location_id`.
+pub fn is_reference(key: &str) -> bool {
+    key != "object_id" && key.ends_with("_id")
+}
+
+fn _objects_by_id(store: &Store) -> AHResult<HashMap<i64, Object>> {
+    Ok(store
+        .all()
+        .iter()?
+        .map(|o| (o["object_id"].as_number().unwrap(), o))
+        .collect())
+}
+
+impl RedoStack {
+    pub fn load(path: impl AsRef<Path>) -> AHResult<Self> {
+        let path = path.as_ref();
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path)?)
+            .with_context(|| format!("failed to read redo log {}", path.display()))?;
+
+        Ok(Self {
+            checkpoint_id: contents["checkpoint_id"].as_i64(),
+            undone: contents["undone"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|u| -> AHResult<UndoneCheckpoint> {
+                    Ok(UndoneCheckpoint {
+                        description: u["description"].as_str().unwrap_or("").to_string(),
+                        restore: serde_json::from_value(u["restore"].clone())?,
+                        remove: serde_json::from_value(u["remove"].clone())?,
+                    })
+                })
+                .collect::<AHResult<_>>()?,
+            remapped_ids: contents["remapped_ids"]
+                .as_object()
+                .into_iter()
+                .flatten()
+                .filter_map(|(k, v)| Some((k.parse().ok()?, v.as_i64()?)))
+                .collect(),
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> AHResult<()> {
+        let contents = json!({
+            "checkpoint_id": self.checkpoint_id,
+            "undone": self.undone.iter().map(|u| json!({
+                "description": u.description,
+                "restore": u.restore,
+                "remove": u.remove,
+            })).collect::<Vec<_>>(),
+            "remapped_ids": self
+                .remapped_ids
+                .iter()
+                .map(|(k, v)| (k.to_string(), json!(v)))
+                .collect::<serde_json::Map<_, _>>(),
+        });
+
+        std::fs::write(path, serde_json::to_string(&contents)?)?;
+
+        Ok(())
+    }
+
+    fn _forget_if_stale(&mut self, store: &Store) {
+        if self.checkpoint_id != _last_checkpoint_id(store) {
+            self.undone.clear();
+            self.remapped_ids.clear();
+        }
+    }
+
+    /// Forgets all but the `keep` most recently undone checkpoints, returning how many were
+    /// forgotten.
+    pub fn forget_oldest(&mut self, keep: usize) -> usize {
+        let forgotten = self.undone.len().saturating_sub(keep);
+        self.undone.drain(..forgotten);
+
+        forgotten
+    }
+
+    fn _remap(&self, mut object_id: i64) -> i64 {
+        while let Some(new_id) = self.remapped_ids.get(&object_id) {
+            object_id = *new_id;
+        }
+
+        object_id
+    }
+
+    /// Undoes the last checkpoint, remembering it so that it can be redone.
+    pub fn undo(&mut self, store: &mut Store) -> AHResult<Option<String>> {
+        self._forget_if_stale(store);
+
+        check_writable()?;
+
+        let before = _objects_by_id(store)?;
+        let description = match store.undo()? {
+            Some(description) => description,
+            None => return Ok(None),
+        };
+        let after = _objects_by_id(store)?;
+
+        self.undone.push(UndoneCheckpoint {
+            description: description.clone(),
+            restore: before
+                .iter()
+                .filter(|(object_id, object)| after.get(object_id) != Some(object))
+                .map(|(_, object)| object.clone())
+                .collect(),
+            remove: after
+                .keys()
+                .filter(|object_id| !before.contains_key(object_id))
+                .copied()
+                .collect(),
+        });
+        self.checkpoint_id = _last_checkpoint_id(store);
+
+        Ok(Some(description))
+    }
+
+    /// Reapplies the most recently undone checkpoint as a new checkpoint.
+    pub fn redo(&mut self, store: &mut Store) -> AHResult<Option<String>> {
+        self._forget_if_stale(store);
+
+        let undone = match self.undone.pop() {
+            Some(undone) => undone,
+            None => return Ok(None),
+        };
+
+        let checkpoint = begin_checkpoint(store)?;
+
+        for object_id in &undone.remove {
+            checkpoint.query(Q.id(self._remap(*object_id))).delete()?;
+        }
+
+        // Objects that refer to others are restored last, so that any re-added objects they refer
+        // to already have their new IDs.
+        let mut restore = undone.restore;
+        restore.sort_by_key(|object| object.keys().any(|k| is_reference(k)));
+
+        for mut object in restore {
+            let object_id = object.remove("object_id").unwrap().as_number().unwrap();
+
+            for (key, value) in object.iter_mut() {
+                if let (true, Some(referenced_id)) = (is_reference(key), value.as_number()) {
+                    *value = PropValue::Number(self._remap(referenced_id));
+                }
+            }
+
+            let current_id = self._remap(object_id);
+            if checkpoint.query(Q.id(current_id)).exists()? {
+                checkpoint.query(Q.id(current_id)).set(object)?;
+            } else {
+                let new_id = checkpoint.add(object)?;
+                self.remapped_ids.insert(current_id, new_id);
+            }
+        }
+
+        commit(checkpoint, &undone.description)?;
+        self.checkpoint_id = _last_checkpoint_id(store);
+
+        Ok(Some(undone.description))
+    }
+}
@@ -7,21 +7,40 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 mod console;
+mod csv;
 mod editor;
+mod history;
 mod types;
 mod utils;
 
 use anyhow::{anyhow, bail, Context, Result as AHResult};
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
 use git_version::git_version;
 use qualia::object;
-use qualia::{Object, Store, Q};
+use qualia::query_builder::QueryBuilder;
+use qualia::{Object, ObjectShape, Queryable, Store, Q};
 use rustyline::Editor;
+use std::collections::{hash_map, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader, IsTerminal, Write};
 
 use crate::console::run_console;
 use crate::editor::run_editor;
-use crate::types::{bin_number_value_parser, Item, ItemLocation, ItemSize, Location};
-use crate::utils::add_item;
+use crate::history::{
+    checkpoint_log, compact_store, is_reference, recent_checkpoint_descriptions, RedoStack,
+};
+use crate::types::{
+    alias_value_parser, align_formatted_items, bin_number_value_parser, parse_item_bin_number,
+    tag_value_parser, Item, ItemLocation, ItemSize, ItemTemplate, Location, SizeFilter,
+    OVERFLOW_BIN,
+};
+use crate::utils::{
+    begin_checkpoint, bin_fullnesses, check_capacity_among, check_unique_name, check_writable,
+    choose_bin_among, choose_bin_in, commit, emptiest_bin, insert_item, insert_items,
+    item_fullness, log_verbose, prepare_item, set_read_only, set_size_weight, set_verbosity,
+    size_weights, CapacityCheck, Failure, FillOrder,
+};
 
 const PACHINKO_VERSION: &str = git_version!(
     prefix = "",
@@ -46,29 +65,86 @@ enum SubCmd {
     #[clap(version = PACHINKO_VERSION, about = "Add a location")]
     AddLocation(AddLocationOpts),
 
+    #[clap(version = PACHINKO_VERSION, about = "Shrink the store and forget old undone actions")]
+    Compact(CompactOpts),
+
+    #[clap(version = PACHINKO_VERSION, about = "Show or change settings")]
+    Config(ConfigOpts),
+
     #[clap(version = PACHINKO_VERSION, about = "Run several commands from an interactive console", visible_alias = "c")]
     Console(CommonOpts),
 
     #[clap(version = PACHINKO_VERSION, about = "Delete an item", visible_alias = "d")]
     Delete(DeleteOpts),
 
+    #[clap(version = PACHINKO_VERSION, about = "Check for items in missing locations or bins")]
+    Doctor(DoctorOpts),
+
     #[clap(version = PACHINKO_VERSION, about = "Dump database contents")]
-    Dump(CommonOpts),
+    Dump(DumpOpts),
 
     #[clap(version = PACHINKO_VERSION, about = "Edit and view items", visible_alias = "e")]
     Editor(CommonOpts),
 
+    #[clap(version = PACHINKO_VERSION, about = "Export items for use in other programs")]
+    Export(ExportOpts),
+
+    #[clap(version = PACHINKO_VERSION, about = "Search items by location, size and name", visible_alias = "f")]
+    Find(FindOpts),
+
+    #[clap(version = PACHINKO_VERSION, about = "Show every change made to the store, oldest first")]
+    History(CommonOpts),
+
+    #[clap(version = PACHINKO_VERSION, about = "Import items from a CSV file made by export")]
+    Import(ImportOpts),
+
     #[clap(version = PACHINKO_VERSION, about = "Show existing items", visible_alias = "i")]
     Items(ItemsOpts),
 
+    #[clap(version = PACHINKO_VERSION, about = "Print a text label for each item")]
+    Labels(LabelsOpts),
+
+    #[clap(version = PACHINKO_VERSION, about = "Show the most recently added items")]
+    Last(LastOpts),
+
     #[clap(version = PACHINKO_VERSION, about = "Show existing locations")]
-    Locations(CommonOpts),
+    Locations(LocationsOpts),
+
+    #[clap(version = PACHINKO_VERSION, about = "Move all items from one location into another and delete it")]
+    MergeLocations(MergeLocationsOpts),
+
+    #[clap(version = PACHINKO_VERSION, about = "Move items to another location or bin", visible_alias = "mv")]
+    Move(MoveOpts),
 
     #[clap(version = PACHINKO_VERSION, about = "Quickly add several items to a location", visible_alias = "qa")]
     Quickadd(QuickaddOpts),
 
+    #[clap(version = PACHINKO_VERSION, about = "Spread a location's items evenly across its bins")]
+    Rebalance(RebalanceOpts),
+
+    #[clap(version = PACHINKO_VERSION, about = "Redo the last undone action", visible_alias = "r")]
+    Redo(CommonOpts),
+
+    #[clap(version = PACHINKO_VERSION, about = "Rename a location")]
+    RenameLocation(RenameLocationOpts),
+
+    #[clap(version = PACHINKO_VERSION, about = "Replace the store's contents with a dump")]
+    Restore(RestoreOpts),
+
+    #[clap(version = PACHINKO_VERSION, about = "Summarize how full each location is", visible_alias = "count")]
+    Stats(StatsOpts),
+
+    #[clap(version = PACHINKO_VERSION, about = "Exchange the items in two bins of a location")]
+    SwapBins(SwapBinsOpts),
+
+    #[clap(version = PACHINKO_VERSION, about = "Add tags to items")]
+    Tag(TagOpts),
+
     #[clap(version = PACHINKO_VERSION, about = "Undo the last action", visible_alias = "u")]
-    Undo(CommonOpts),
+    Undo(UndoOpts),
+
+    #[clap(version = PACHINKO_VERSION, about = "Remove tags from items")]
+    Untag(TagOpts),
 }
 
 impl SubCmd {
@@ -77,43 +153,155 @@ impl SubCmd {
             SubCmd::Add(o) => run_add(o),
             SubCmd::AddLocation(o) => run_add_location(o),
             SubCmd::Delete(o) => run_delete(o),
+            SubCmd::Compact(o) => run_compact(o),
+            SubCmd::Config(o) => run_config(o),
+            SubCmd::Doctor(o) => run_doctor(o),
             SubCmd::Dump(o) => run_dump(o),
             SubCmd::Console(o) => run_console(o),
             SubCmd::Editor(o) => run_editor(o),
+            SubCmd::Export(o) => run_export(o),
+            SubCmd::Find(o) => run_find(o),
+            SubCmd::History(o) => run_history(o),
+            SubCmd::Import(o) => run_import(o),
             SubCmd::Items(o) => run_items(o),
+            SubCmd::Labels(o) => run_labels(o),
+            SubCmd::Last(o) => run_last(o),
             SubCmd::Locations(o) => run_locations(o),
+            SubCmd::MergeLocations(o) => run_merge_locations(o),
+            SubCmd::Move(o) => run_move(o),
             SubCmd::Quickadd(o) => run_quickadd(o),
+            SubCmd::Rebalance(o) => run_rebalance(o),
+            SubCmd::Redo(o) => run_redo(o),
+            SubCmd::RenameLocation(o) => run_rename_location(o),
+            SubCmd::Restore(o) => run_restore(o),
+            SubCmd::Stats(o) => run_stats(o),
+            SubCmd::SwapBins(o) => run_swap_bins(o),
+            SubCmd::Tag(o) => _change_tags(o, true),
             SubCmd::Undo(o) => run_undo(o),
+            SubCmd::Untag(o) => _change_tags(o, false),
         }
     }
 }
 
 #[derive(Parser, Debug)]
 struct CommonOpts {
-    #[clap(long, env = "PACHINKO_STORE_PATH")]
+    #[clap(
+        long,
+        env = "PACHINKO_STORE_PATH",
+        help = "Store to use; otherwise, the nearest .pachinko.qualia if PACHINKO_STORE=auto, or the default store"
+    )]
     store_path: Option<String>,
+    #[clap(
+        short,
+        long,
+        action = clap::ArgAction::Count,
+        help = "Print what is done to the store to stderr; repeat to also show bin fullnesses"
+    )]
+    verbose: u8,
+    #[clap(long, help = "Fail instead of changing anything in the store")]
+    read_only: bool,
+    #[clap(short, long, help = "Don't print the items that were added or deleted")]
+    quiet: bool,
 }
 
 impl CommonOpts {
+    fn data_dir_path() -> AHResult<std::path::PathBuf> {
+        let data_dir_path = dirs::data_dir()
+            .ok_or(anyhow!(
+                "Could not determine your home directory; is $HOME set?"
+            ))?
+            .join("pachinko");
+
+        if !data_dir_path.is_dir() {
+            std::fs::create_dir_all(&data_dir_path)?;
+        }
+
+        Ok(data_dir_path)
+    }
+
+    /// Looks for a `.pachinko.qualia` in the current directory or any of its parents, if
+    /// `PACHINKO_STORE=auto` is set.
+    fn find_local_store_path() -> AHResult<Option<String>> {
+        match std::env::var("PACHINKO_STORE") {
+            Ok(mode) if mode == "auto" => {}
+            Ok(_) => bail!(
+                "PACHINKO_STORE can only be set to auto; use PACHINKO_STORE_PATH to choose a store"
+            ),
+            Err(_) => return Ok(None),
+        }
+
+        Ok(std::env::current_dir()?
+            .ancestors()
+            .map(|dir| dir.join(".pachinko.qualia"))
+            .find(|path| path.is_file())
+            .map(|path| path.to_string_lossy().into_owned()))
+    }
+
+    /// The store to use, from the first of: `--store-path`, `$PACHINKO_STORE_PATH`, a store found
+    /// by `find_local_store_path`, or `pachinko.qualia` in the data directory.
+    fn resolved_store_path(&self) -> AHResult<String> {
+        if let Some(s) = &self.store_path {
+            return Ok(s.clone());
+        }
+
+        Ok(match Self::find_local_store_path()? {
+            Some(s) => s,
+            None => format!(
+                "{}/pachinko.qualia",
+                Self::data_dir_path()?.to_str().unwrap(),
+            ),
+        })
+    }
+
     fn open_store(&self) -> AHResult<Store> {
-        let store_path = match &self.store_path {
-            Some(s) => s.clone(),
-            None => {
-                let data_dir_path = dirs::data_dir()
-                    .ok_or(anyhow!(
-                        "Could not determine your home directory; is $HOME set?"
-                    ))?
-                    .join("pachinko");
-
-                if !data_dir_path.is_dir() {
-                    std::fs::create_dir_all(&data_dir_path)?;
-                }
+        set_verbosity(self.verbose);
+        set_read_only(self.read_only);
+
+        let store_path = self.resolved_store_path()?;
+        log_verbose(1, format!("store: {}", store_path));
+
+        // Opening a store creates it if it doesn't exist.
+        if self.read_only && !std::path::Path::new(&store_path).exists() {
+            bail!(Failure::Store(format!(
+                "store {} does not exist",
+                store_path
+            )));
+        }
 
-                format!("{}/pachinko.qualia", data_dir_path.to_str().unwrap(),)
+        if self.store_path.is_some() {
+            if let Some(parent) = std::path::Path::new(&store_path).parent() {
+                if !parent.as_os_str().is_empty() && !parent.is_dir() {
+                    std::fs::create_dir_all(parent).with_context(|| {
+                        Failure::Store(format!(
+                            "failed to create directory {} for store",
+                            parent.display()
+                        ))
+                    })?;
+                }
             }
-        };
+        }
+
+        Store::open(store_path).context(Failure::Store("failed to open store".to_string()))
+    }
+
+    /// Where undone actions are kept so that `redo` can reapply them.
+    fn redo_log_path(&self) -> AHResult<String> {
+        Ok(self.resolved_store_path()? + ".redo")
+    }
+
+    /// Where commands entered in the console are kept between sessions.
+    fn console_history_path(&self) -> AHResult<String> {
+        Ok(self.resolved_store_path()? + ".history")
+    }
+
+    /// Where the editor remembers which item was selected when it was last closed.
+    fn editor_position_path(&self) -> AHResult<String> {
+        Ok(self.resolved_store_path()? + ".editor")
+    }
 
-        Store::open(store_path).context("failed to open store")
+    /// Where the editor looks for its color theme.
+    fn theme_path(&self) -> AHResult<std::path::PathBuf> {
+        Ok(Self::data_dir_path()?.join("theme.toml"))
     }
 }
 
@@ -127,10 +315,59 @@ struct AddOpts {
     common: CommonOpts,
     #[clap()]
     location: ItemLocation,
-    #[clap()]
-    name: String,
-    #[clap(value_enum, default_value = "S")]
-    size: ItemSize,
+    #[clap(
+        required = true,
+        value_name = "NAME",
+        help = "Names of the items to add, optionally followed by their size (S, M, L or X)"
+    )]
+    names: Vec<String>,
+    #[clap(
+        long,
+        value_enum,
+        ignore_case = true,
+        help = "Size of the items to add [default: S]"
+    )]
+    size: Option<ItemSize>,
+    #[clap(short = 'n', long, default_value = "1", value_parser = clap::value_parser!(i64).range(1..))]
+    count: i64,
+    #[clap(long, value_parser = clap::value_parser!(i64).range(1..), help = "How much room the item takes up, in place of its size's weight from `pachinko config`")]
+    volume: Option<i64>,
+    #[clap(
+        long,
+        help = "Fail instead of warning when the bin would be over capacity"
+    )]
+    strict: bool,
+    #[clap(
+        long,
+        visible_alias = "allow-duplicate",
+        help = "Add the item even if the location already has one with the same name"
+    )]
+    force: bool,
+    #[clap(long = "tag", value_parser = tag_value_parser, help = "Tag the item (may be repeated)")]
+    tags: Vec<String>,
+    #[clap(long, help = "Show where the item would go without adding it")]
+    dry_run: bool,
+    #[clap(
+        long,
+        help = "Only use a location with exactly this name, including case, rather than one whose name contains its words"
+    )]
+    exact: bool,
+    #[clap(
+        long,
+        value_name = "NUM_BINS",
+        num_args = 0..=1,
+        default_missing_value = "1",
+        value_parser = bin_number_value_parser,
+        help = "Add the location (with 1 bin unless given) if it doesn't exist"
+    )]
+    create_location: Option<i64>,
+    #[clap(
+        long,
+        value_enum,
+        default_value_t,
+        help = "How to choose between equally empty bins"
+    )]
+    fill_order: FillOrder,
 }
 
 impl WithCommonOpts for AddOpts {
@@ -139,40 +376,281 @@ impl WithCommonOpts for AddOpts {
     }
 }
 
-fn _resolve_location(store: &Store, location: &ItemLocation) -> AHResult<Location> {
-    let matching_locations = store.query(
-        Q.equal("type", "location")
-            .like("name", location.location.clone()),
-    );
+/// Narrows the query to objects whose name is exactly the given one, or, unless `exact`, ones whose
+/// name contains the pattern's words in order, ignoring case (`*` matches the rest of a word).
+fn _name_query(query: QueryBuilder, name_pattern: &str, exact: bool) -> QueryBuilder {
+    if exact {
+        query.equal("name", name_pattern)
+    } else {
+        query.like("name", name_pattern)
+    }
+}
 
-    if matching_locations.len()? != 1 {
-        bail!(
-            "location name \"{}\" did not match exactly one location",
-            location.location
-        );
+fn _find_location_by_alias(store: &Store, alias: &str) -> AHResult<Option<Location>> {
+    Ok(store
+        .query(Q.equal("type", "location"))
+        .iter_as::<Location>()?
+        .find(|l| l.alias().is_some_and(|a| a.eq_ignore_ascii_case(alias))))
+}
+
+/// Find the single location matching the given name pattern.
+///
+/// A location whose alias matches exactly (ignoring case) is preferred over name matches, and a
+/// location whose name matches exactly is preferred over other locations matching the pattern.
+fn _resolve_location(store: &Store, name_pattern: &str) -> AHResult<Location> {
+    _resolve_location_matching(store, name_pattern, false)
+}
+
+/// Like `_resolve_location`, but if `exact`, only a location with exactly that name (including
+/// case) will do.
+fn _resolve_location_matching(
+    store: &Store,
+    name_pattern: &str,
+    exact: bool,
+) -> AHResult<Location> {
+    if !exact {
+        if let Some(aliased_location) = _find_location_by_alias(store, name_pattern)? {
+            return Ok(aliased_location);
+        }
+    }
+
+    let mut matching_locations = store
+        .query(_name_query(
+            Q.equal("type", "location"),
+            name_pattern,
+            exact,
+        ))
+        .iter_as::<Location>()?
+        .collect::<Vec<_>>();
+
+    if matching_locations.len() > 1 {
+        let exactly_named = matching_locations
+            .iter()
+            .filter(|l| l.name == name_pattern)
+            .count();
+
+        if exactly_named == 1 {
+            matching_locations.retain(|l| l.name == name_pattern);
+        }
+    }
+
+    match matching_locations.len() {
+        0 => match _closest_location(store, name_pattern)? {
+            Some(closest_location) => bail!(Failure::NotFound(format!(
+                "location name \"{}\" did not match exactly one location; did you mean \"{}\"?",
+                name_pattern, closest_location.name
+            ))),
+            None => bail!(Failure::NotFound(format!(
+                "location name \"{}\" did not match exactly one location",
+                name_pattern
+            ))),
+        },
+        1 => Ok(matching_locations.pop().unwrap()),
+        _ => {
+            matching_locations.sort_by(|a, b| a.name.cmp(&b.name));
+            _choose_location(name_pattern, matching_locations)
+        }
+    }
+}
+
+/// The location whose name is the best fuzzy match for the pattern, if any are close.
+fn _closest_location(store: &Store, name_pattern: &str) -> AHResult<Option<Location>> {
+    let matcher = SkimMatcherV2::default().ignore_case();
+
+    Ok(store
+        .query(Q.equal("type", "location"))
+        .iter_as::<Location>()?
+        .filter_map(|l| Some((matcher.fuzzy_match(&l.name, name_pattern)?, l)))
+        .max_by_key(|(score, _)| *score)
+        .map(|(_, l)| l))
+}
+
+/// Asks which of the locations was meant, or fails listing them if there's no terminal to ask on.
+fn _choose_location(name_pattern: &str, mut locations: Vec<Location>) -> AHResult<Location> {
+    let names = locations
+        .iter()
+        .map(|l| l.name.as_str())
+        .collect::<Vec<_>>();
+
+    if !std::io::stdin().is_terminal() {
+        bail!(Failure::Ambiguous(format!(
+            "location name \"{}\" did not match exactly one location; it matched {}",
+            name_pattern,
+            names.join(", ")
+        )));
+    }
+
+    for (i, name) in names.iter().enumerate() {
+        println!("{:>4}. {}", i + 1, name);
     }
+    print!(
+        "Which location did \"{}\" mean? [1-{}] ",
+        name_pattern,
+        names.len()
+    );
+    std::io::stdout().flush()?;
 
-    Ok(matching_locations.iter_as()?.next().unwrap())
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+
+    match answer.trim().parse::<usize>() {
+        Ok(choice) if (1..=locations.len()).contains(&choice) => Ok(locations.remove(choice - 1)),
+        _ => bail!("no location chosen"),
+    }
 }
 
 fn run_add(opts: AddOpts) -> AHResult<()> {
     let mut store = opts.common.open_store()?;
 
-    // eprintln!("{:#?}", store.all().iter()?.collect::<Vec<Object>>());
+    let location = match (
+        _resolve_location_matching(&store, &opts.location.location, opts.exact),
+        opts.create_location,
+    ) {
+        (Ok(location), _) => location,
+        // Left without an ID, so that it is added along with the item. Any other failure, like
+        // an ambiguous name, still stops the add.
+        (Err(e), Some(num_bins)) if matches!(e.downcast_ref(), Some(Failure::NotFound(_))) => {
+            Location {
+                object_id: None,
+                name: opts.location.location.clone(),
+                num_bins,
+                rest: Object::new(),
+            }
+        }
+        (Err(e), _) => return Err(e),
+    };
 
-    let location = _resolve_location(&store, &opts.location)?;
+    let mut names = opts.names;
+    let mut size = opts.size;
 
-    println!(
-        "{}",
-        add_item(
-            &mut store,
-            opts.name,
+    // A lone name is always a name, so that an item can be called "M".
+    if names.len() > 1 {
+        if let Ok(trailing_size) = ItemSize::from_str(names.last().unwrap(), true) {
+            if size.is_some() {
+                bail!("size given both as --size and after the names");
+            }
+
+            size = Some(trailing_size);
+            names.pop();
+        }
+    }
+
+    if !opts.force {
+        if location.object_id.is_some() {
+            for name in &names {
+                check_unique_name(&store, name, &location)?;
+            }
+        }
+
+        if let Some((_, name)) = names
+            .iter()
+            .enumerate()
+            .find(|(i, name)| names[..*i].iter().any(|n| n.eq_ignore_ascii_case(name)))
+        {
+            bail!(
+                "{} was given more than once; use --force to add it anyway",
+                name
+            );
+        }
+    }
+
+    let bins = opts.location.bins_in(&location)?;
+    let weights = size_weights(&store)?;
+    // Kept up to date as items are chosen bins, so that each one sees where the earlier ones went.
+    let mut fullnesses = match location.object_id {
+        Some(location_id) => bin_fullnesses(&store, location_id, location.num_bins)?,
+        None => (1..=location.num_bins).map(|bin_no| (bin_no, 0)).collect(),
+    };
+    let mut chooser_location = location.clone();
+
+    let mut items = vec![];
+    for name in names {
+        let bin_no = match opts.location.bin {
+            Some(bin_no) => bin_no,
+            None => choose_bin_among(
+                &fullnesses,
+                &chooser_location,
+                bins.clone(),
+                opts.fill_order,
+            ),
+        };
+
+        let mut item = prepare_item(
+            &store,
+            name,
             &location,
-            opts.location.bin,
-            opts.size,
-        )?
-        .format_with_store(&store)?
-    );
+            Some(bin_no),
+            size.unwrap_or(ItemSize::S),
+            opts.count,
+            CapacityCheck::Ignore,
+        )?;
+        if let Some(volume) = opts.volume {
+            item.set_volume(volume);
+        }
+        if !opts.tags.is_empty() {
+            item.set_tags(&opts.tags);
+        }
+
+        check_capacity_among(
+            &fullnesses,
+            &weights,
+            &item,
+            if opts.strict {
+                CapacityCheck::Strict
+            } else {
+                CapacityCheck::Warn
+            },
+        )?;
+
+        if let Some(fullness) = fullnesses.get_mut(&item.bin_no) {
+            *fullness += item_fullness(&weights, &item)?;
+        }
+        chooser_location
+            .rest
+            .insert("last_bin".to_string(), item.bin_no.into());
+
+        items.push(item);
+    }
+
+    if !opts.dry_run {
+        insert_items(&mut store, &mut items, opts.fill_order)?;
+    }
+
+    if opts.common.quiet {
+        return Ok(());
+    }
+
+    for item in &items {
+        if opts.dry_run {
+            println!("(dry run) {}", item.format_with_store(&store)?);
+        } else {
+            println!("{}", item.format_with_store(&store)?);
+        }
+    }
+
+    Ok(())
+}
+
+fn _print_added_item(
+    store: &mut Store,
+    item: &mut Item,
+    dry_run: bool,
+    fill_order: FillOrder,
+    quiet: bool,
+) -> AHResult<()> {
+    if !dry_run {
+        insert_item(store, item, fill_order)?;
+    }
+
+    if quiet {
+        return Ok(());
+    }
+
+    if dry_run {
+        println!("(dry run) {}", item.format_with_store(store)?);
+    } else {
+        println!("{}", item.format_with_store(store)?);
+    }
 
     Ok(())
 }
@@ -185,6 +663,12 @@ struct AddLocationOpts {
     name: String,
     #[clap(value_parser = bin_number_value_parser)]
     num_bins: i64,
+    #[clap(long, value_parser = alias_value_parser)]
+    alias: Option<String>,
+    #[clap(long, value_parser = clap::value_parser!(i64).range(1..), help = "How much each bin can hold, in the same units as the size weights set with `pachinko config`")]
+    capacity: Option<i64>,
+    #[clap(long, help = "Group the location under this one")]
+    parent: Option<String>,
 }
 
 impl WithCommonOpts for AddLocationOpts {
@@ -196,188 +680,2012 @@ impl WithCommonOpts for AddLocationOpts {
 fn run_add_location(opts: AddLocationOpts) -> AHResult<()> {
     let mut store = opts.common.open_store()?;
 
-    let checkpoint = store.checkpoint()?;
-    checkpoint.add(object!(
+    let mut location = object!(
         "type" => "location",
         "name" => &opts.name,
         "num_bins" => opts.num_bins,
-    ))?;
-    checkpoint.commit(format!("add location {}", &opts.name))?;
+    );
 
-    Ok(())
-}
+    if let Some(alias) = &opts.alias {
+        if let Some(existing_location) = _find_location_by_alias(&store, alias)? {
+            bail!(
+                "alias \"{}\" is already used by location {}",
+                alias,
+                existing_location.name
+            );
+        }
 
-fn run_dump(opts: CommonOpts) -> AHResult<()> {
-    let store = opts.open_store()?;
+        location.insert("alias".into(), alias.into());
+    }
 
-    serde_json::to_writer(std::io::stdout(), &store.all().iter()?.collect::<Vec<_>>())?;
+    if let Some(capacity) = opts.capacity {
+        location.insert("capacity".into(), capacity.into());
+    }
 
-    Ok(())
-}
+    if let Some(parent) = &opts.parent {
+        let parent_location = _resolve_location(&store, parent)?;
 
-fn _format_items(
-    store: &Store,
-    items: &qualia::Collection,
-) -> AHResult<impl Iterator<Item = impl std::fmt::Display>> {
-    let mut formatted_items = items
-        .iter_converted::<Item>(&store)?
-        .map(|item| item.format_with_store(store))
-        .collect::<AHResult<Vec<_>>>()?;
-    formatted_items.sort();
+        location.insert(
+            "parent_id".into(),
+            parent_location.object_id.unwrap().into(),
+        );
+    }
 
-    Ok(formatted_items.into_iter())
+    let checkpoint = begin_checkpoint(&mut store)?;
+    checkpoint.add(location)?;
+    commit(checkpoint, format!("add location {}", &opts.name))?;
+
+    Ok(())
 }
 
-#[derive(Args, Debug)]
-struct ItemsOpts {
+#[derive(Args)]
+struct RenameLocationOpts {
     #[clap(flatten)]
     common: CommonOpts,
     #[clap()]
-    name_pattern: Option<String>,
+    location: String,
+    #[clap()]
+    new_name: String,
 }
 
-impl WithCommonOpts for ItemsOpts {
+impl WithCommonOpts for RenameLocationOpts {
     fn common_opts(&self) -> &CommonOpts {
         &self.common
     }
 }
 
-fn run_items(opts: ItemsOpts) -> AHResult<()> {
-    let store = opts.common_opts().open_store()?;
+fn run_rename_location(opts: RenameLocationOpts) -> AHResult<()> {
+    let mut store = opts.common.open_store()?;
 
-    let mut query = Q.equal("type", "item");
+    let location = _resolve_location(&store, &opts.location)?;
 
-    if let Some(name_pattern) = opts.name_pattern {
-        query = query.like("name", &name_pattern);
+    if let Some(existing_location) = store
+        .query(Q.equal("type", "location"))
+        .iter_as::<Location>()?
+        .find(|l| l.object_id != location.object_id && l.name.eq_ignore_ascii_case(&opts.new_name))
+    {
+        bail!("location {} already exists", existing_location.name);
     }
 
-    for formatted_item in _format_items(&store, &store.query(query))? {
-        println!("{}", formatted_item);
-    }
+    let checkpoint = begin_checkpoint(&mut store)?;
+    checkpoint
+        .query(Location::q().id(location.object_id.unwrap()))
+        .set(object!("name" => &opts.new_name))?;
+    commit(
+        checkpoint,
+        format!("rename location {} to {}", location.name, opts.new_name),
+    )?;
 
     Ok(())
 }
 
 #[derive(Args)]
-struct DeleteOpts {
+struct MergeLocationsOpts {
     #[clap(flatten)]
     common: CommonOpts,
-    #[clap(short, long)]
-    all: bool,
     #[clap()]
-    name_pattern: String,
+    source: String,
+    #[clap()]
+    destination: String,
 }
 
-impl WithCommonOpts for DeleteOpts {
+impl WithCommonOpts for MergeLocationsOpts {
     fn common_opts(&self) -> &CommonOpts {
         &self.common
     }
 }
 
-fn run_delete(opts: DeleteOpts) -> AHResult<()> {
+fn run_merge_locations(opts: MergeLocationsOpts) -> AHResult<()> {
     let mut store = opts.common.open_store()?;
 
-    let checkpoint = store.checkpoint()?;
-    let matching_items = checkpoint.query(Q.equal("type", "item").like("name", &opts.name_pattern));
-
-    if matching_items.len()? > 1 && !opts.all {
-        let formatted_items: Vec<_> = _format_items(&checkpoint, &matching_items)?
-            .map(|item| format!("    {}", item))
-            .collect();
+    let source = _resolve_location(&store, &opts.source)?;
+    let destination = _resolve_location(&store, &opts.destination)?;
 
-        bail!(
-            "found multiple matching items (use --all to delete multiple items):\n{}",
-            formatted_items.join("\n")
-        );
+    if source.object_id == destination.object_id {
+        bail!("cannot merge location {} into itself", source.name);
     }
 
-    for formatted_item in _format_items(&checkpoint, &matching_items)? {
-        println!("Deleted {}", formatted_item);
+    let weights = size_weights(&store)?;
+    let mut fullnesses =
+        bin_fullnesses(&store, destination.object_id.unwrap(), destination.num_bins)?;
+
+    let mut source_items = store
+        .query(
+            Q.equal("type", "item")
+                .equal("location_id", source.object_id.unwrap()),
+        )
+        .iter_converted::<Item>(&store)?
+        .collect::<Vec<_>>();
+    // Items that still fit keep their bins, so place them before choosing bins for the rest.
+    source_items.sort_by_key(|item| item.bin_no > destination.num_bins);
+
+    // Children of the source move under the destination, unless the destination is itself
+    // somewhere below the source; then they take the source's place in the tree instead.
+    let new_parent_id = if _is_location_within(&store, &destination, source.object_id.unwrap())? {
+        source.parent_id()
+    } else {
+        destination.object_id
+    };
+
+    let checkpoint = begin_checkpoint(&mut store)?;
+
+    for item in source_items {
+        let bin_no = if item.bin_no > destination.num_bins {
+            emptiest_bin(&fullnesses, destination.num_bins)
+        } else {
+            item.bin_no
+        };
+        if let Some(fullness) = fullnesses.get_mut(&bin_no) {
+            *fullness += item_fullness(&weights, &item)?;
+        }
+
+        checkpoint
+            .query(Item::q().id(item.object_id.unwrap()))
+            .set(object!(
+                "location_id" => destination.object_id.unwrap(),
+                "bin_no" => bin_no,
+            ))?;
     }
 
-    matching_items.delete()?;
+    if let Some(new_parent_id) = new_parent_id {
+        checkpoint
+            .query(Location::q().equal("parent_id", source.object_id.unwrap()))
+            .set(object!("parent_id" => new_parent_id))?;
+    }
 
-    checkpoint.commit(format!("delete items matching {}", &opts.name_pattern))?;
+    checkpoint
+        .query(Location::q().id(source.object_id.unwrap()))
+        .delete()?;
+    commit(
+        checkpoint,
+        format!("merge location {} into {}", source.name, destination.name),
+    )?;
 
     Ok(())
 }
 
-fn run_locations(opts: CommonOpts) -> AHResult<()> {
-    let store = opts.open_store()?;
-
-    for location in store
+/// Whether `location` sits anywhere below the location with `ancestor_id`.
+fn _is_location_within(store: &Store, location: &Location, ancestor_id: i64) -> AHResult<bool> {
+    let locations = store
         .query(Q.equal("type", "location"))
         .iter_as::<Location>()?
-    {
-        if location.num_bins > 1 {
-            println!("{} ({} bins)", location.name, location.num_bins);
-        } else {
-            println!("{}", location.name);
+        .collect::<Vec<_>>();
+
+    let mut parent_id = location.parent_id();
+    // Bounded by the number of locations in case the stored parents form a loop.
+    for _ in 0..locations.len() {
+        match parent_id {
+            Some(id) if id == ancestor_id => return Ok(true),
+            Some(id) => {
+                parent_id = locations
+                    .iter()
+                    .find(|l| l.object_id == Some(id))
+                    .and_then(|l| l.parent_id())
+            }
+            None => break,
         }
     }
 
-    Ok(())
+    Ok(false)
+}
+
+fn _parse_config_setting(s: &str) -> Result<ItemSize, String> {
+    s.strip_prefix("size.")
+        .and_then(|size| size.parse::<ItemSize>().ok())
+        .ok_or_else(|| "must be one of size.S, size.M, size.L or size.X".to_string())
 }
 
 #[derive(Args)]
-struct QuickaddOpts {
+struct ConfigOpts {
     #[clap(flatten)]
     common: CommonOpts,
-    #[clap()]
-    location: ItemLocation,
+    #[clap(
+        value_parser = _parse_config_setting,
+        help = "Setting to show or change, like size.M (how much room medium items take up)"
+    )]
+    setting: Option<ItemSize>,
+    #[clap(value_parser = clap::value_parser!(i64).range(1..), requires = "setting")]
+    value: Option<i64>,
 }
 
-fn run_quickadd(opts: QuickaddOpts) -> AHResult<()> {
-    let mut store = opts.common.open_store()?;
+impl WithCommonOpts for ConfigOpts {
+    fn common_opts(&self) -> &CommonOpts {
+        &self.common
+    }
+}
 
-    // eprintln!("{:#?}", store.all().iter()?.collect::<Vec<Object>>());
+fn run_config(opts: ConfigOpts) -> AHResult<()> {
+    let mut store = opts.common.open_store()?;
 
-    let location = _resolve_location(&store, &opts.location)?;
+    if let (Some(size), Some(value)) = (opts.setting, opts.value) {
+        return set_size_weight(&mut store, size, value);
+    }
 
-    let bin_number_display = match opts.location.bin {
-        Some(bin_no) => format!("/{}", bin_no),
-        None => "".to_string(),
+    let weights = size_weights(&store)?;
+    let sizes = match opts.setting {
+        Some(size) => vec![size],
+        None => ItemSize::value_variants().to_vec(),
     };
-    let prompt = location.name.clone() + &bin_number_display + "> ";
 
-    let mut rl = Editor::<()>::new()?;
+    for size in sizes {
+        println!("size.{} = {}", size, weights.get(size));
+    }
+
+    Ok(())
+}
 
-    while let Ok(line) = rl.readline(&prompt) {
-        let mut name = line.trim().to_string();
-        let mut size = ItemSize::S;
+#[derive(Args)]
+struct DoctorOpts {
+    #[clap(flatten)]
+    common: CommonOpts,
+    #[clap(
+        long,
+        help = "Move items in missing locations to an Unsorted location, and items in missing bins to the emptiest bin"
+    )]
+    fix: bool,
+}
 
-        if let Some(cap) = regex::Regex::new(r"^(.*?)\s+([SMLX])$")?.captures(line.trim()) {
-            name = cap[1].to_string();
-            size = cap[2].parse()?;
+impl WithCommonOpts for DoctorOpts {
+    fn common_opts(&self) -> &CommonOpts {
+        &self.common
+    }
+}
+
+fn run_doctor(opts: DoctorOpts) -> AHResult<()> {
+    let mut store = opts.common.open_store()?;
+
+    let locations: HashMap<i64, Location> = store
+        .query(Q.equal("type", "location"))
+        .iter_as::<Location>()?
+        .map(|l| (l.object_id.unwrap(), l))
+        .collect();
+
+    // Items are read as plain objects, as converting them fails for missing locations.
+    let mut orphaned_item_ids = vec![];
+    let mut misbinned_item_ids = vec![];
+    for object in store.query(Q.equal("type", "item")).iter()? {
+        let object_id = object["object_id"].as_number().unwrap();
+        let name = object
+            .get("name")
+            .and_then(|n| n.as_str())
+            .cloned()
+            .unwrap_or_default();
+        let bin_no = object.get("bin_no").and_then(|b| b.as_number());
+
+        match object
+            .get("location_id")
+            .and_then(|l| l.as_number())
+            .and_then(|location_id| locations.get(&location_id))
+        {
+            None => {
+                println!(
+                    "Item {} ({}) is not in an existing location",
+                    object_id, name
+                );
+                orphaned_item_ids.push(object_id);
+            }
+            Some(location) => match bin_no {
+                None => {
+                    println!(
+                        "Item {} ({}) is in {} but has no bin",
+                        object_id, name, location.name
+                    );
+                    misbinned_item_ids.push(object_id);
+                }
+                Some(bin_no)
+                    if bin_no != OVERFLOW_BIN && !(1..=location.num_bins).contains(&bin_no) =>
+                {
+                    println!(
+                        "Item {} ({}) is in bin {} of {}, which only has {} bins",
+                        object_id, name, bin_no, location.name, location.num_bins
+                    );
+                    misbinned_item_ids.push(object_id);
+                }
+                Some(_) => {}
+            },
         }
+    }
 
-        println!(
-            "{}",
-            add_item(
-                &mut store,
-                name.to_string(),
-                &location,
-                opts.location.bin,
-                size,
-            )?
-            .format_with_store(&store)?
-        );
+    let num_problems = orphaned_item_ids.len() + misbinned_item_ids.len();
+    if num_problems == 0 {
+        println!("No problems found");
+        return Ok(());
     }
 
-    Ok(())
-}
+    if !opts.fix {
+        return Ok(());
+    }
 
-fn run_undo(opts: CommonOpts) -> AHResult<()> {
-    let mut store = opts.open_store()?;
+    let weights = size_weights(&store)?;
+    let mut fullnesses: HashMap<i64, HashMap<i64, i64>> = HashMap::new();
+    let mut new_bins = vec![];
+    for object_id in misbinned_item_ids {
+        let mut object = store.query(Q.id(object_id)).one()?;
+        // Items without a bin can't be converted until they have one; it is replaced below.
+        object.entry("bin_no".into()).or_insert(OVERFLOW_BIN.into());
+        let item = Item::try_convert(object, &store)?;
+        let location = &item.location;
+        let location_fullnesses = match fullnesses.entry(location.object_id.unwrap()) {
+            hash_map::Entry::Occupied(e) => e.into_mut(),
+            hash_map::Entry::Vacant(e) => e.insert(bin_fullnesses(
+                &store,
+                location.object_id.unwrap(),
+                location.num_bins,
+            )?),
+        };
 
-    match store.undo()? {
-        Some(description) => println!("Undid: {}", description),
-        None => println!("Nothing to undo"),
+        let bin_no = emptiest_bin(location_fullnesses, location.num_bins);
+        *location_fullnesses.get_mut(&bin_no).unwrap() += item_fullness(&weights, &item)?;
+        new_bins.push((object_id, bin_no));
     }
 
-    Ok(())
-}
+    let unsorted_location = locations
+        .values()
+        .find(|l| l.name.eq_ignore_ascii_case("Unsorted"))
+        .cloned();
+
+    let checkpoint = begin_checkpoint(&mut store)?;
+
+    if !orphaned_item_ids.is_empty() {
+        let unsorted_location_id = match unsorted_location {
+            Some(location) => location.object_id.unwrap(),
+            None => {
+                let mut location = Location {
+                    object_id: None,
+                    name: "Unsorted".to_string(),
+                    num_bins: 1,
+                    rest: Object::new(),
+                };
+                checkpoint.add_with_id(&mut location)?;
+
+                location.object_id.unwrap()
+            }
+        };
 
-fn main() -> AHResult<()> {
-    Opts::parse().subcmd.invoke()
+        for object_id in orphaned_item_ids {
+            checkpoint
+                .query(Q.id(object_id))
+                .set(object!("location_id" => unsorted_location_id, "bin_no" => 1))?;
+        }
+    }
+
+    for (object_id, bin_no) in new_bins {
+        checkpoint
+            .query(Q.id(object_id))
+            .set(object!("bin_no" => bin_no))?;
+    }
+
+    commit(
+        checkpoint,
+        format!("fix {} problems found by doctor", num_problems),
+    )?;
+
+    println!("Fixed {} problems", num_problems);
+
+    Ok(())
+}
+
+#[derive(Args)]
+struct RebalanceOpts {
+    #[clap(flatten)]
+    common: CommonOpts,
+    #[clap()]
+    location: String,
+}
+
+impl WithCommonOpts for RebalanceOpts {
+    fn common_opts(&self) -> &CommonOpts {
+        &self.common
+    }
+}
+
+fn run_rebalance(opts: RebalanceOpts) -> AHResult<()> {
+    let mut store = opts.common.open_store()?;
+
+    let location = _resolve_location(&store, &opts.location)?;
+    let weights = size_weights(&store)?;
+
+    let mut items = store
+        .query(
+            Q.equal("type", "item")
+                .equal("location_id", location.object_id.unwrap()),
+        )
+        .iter_converted::<Item>(&store)?
+        // Overflow items are waiting for room, not taking it up.
+        .filter(|item| item.bin_no != OVERFLOW_BIN)
+        .map(|item| Ok((item_fullness(&weights, &item)?, item)))
+        .collect::<AHResult<Vec<_>>>()?;
+    // Placing the biggest items first leaves the small ones to even things out.
+    items.sort_by_cached_key(|(fullness, item)| (std::cmp::Reverse(*fullness), item.format()));
+
+    let mut fullnesses: HashMap<i64, i64> = (1..=location.num_bins).map(|b| (b, 0)).collect();
+    let mut moves = vec![];
+    for (fullness, item) in &items {
+        let emptiest = emptiest_bin(&fullnesses, location.num_bins);
+        // Items stay put when their bin is as empty as any other.
+        let bin_no = if fullnesses.get(&item.bin_no) == Some(&fullnesses[&emptiest]) {
+            item.bin_no
+        } else {
+            emptiest
+        };
+        *fullnesses.get_mut(&bin_no).unwrap() += fullness;
+
+        if bin_no != item.bin_no {
+            moves.push((item.object_id.unwrap(), bin_no));
+        }
+    }
+
+    if !moves.is_empty() {
+        let checkpoint = begin_checkpoint(&mut store)?;
+
+        for (object_id, bin_no) in &moves {
+            checkpoint
+                .query(Item::q().id(*object_id))
+                .set(object!("bin_no" => *bin_no))?;
+        }
+
+        commit(checkpoint, format!("rebalance location {}", location.name))?;
+    }
+
+    println!(
+        "Moved {} of {} item{}",
+        moves.len(),
+        items.len(),
+        if items.len() == 1 { "" } else { "s" }
+    );
+
+    Ok(())
+}
+
+#[derive(Args)]
+struct SwapBinsOpts {
+    #[clap(flatten)]
+    common: CommonOpts,
+    #[clap()]
+    location: String,
+    #[clap(value_parser = bin_number_value_parser)]
+    first_bin: i64,
+    #[clap(value_parser = bin_number_value_parser)]
+    second_bin: i64,
+}
+
+impl WithCommonOpts for SwapBinsOpts {
+    fn common_opts(&self) -> &CommonOpts {
+        &self.common
+    }
+}
+
+fn run_swap_bins(opts: SwapBinsOpts) -> AHResult<()> {
+    let mut store = opts.common.open_store()?;
+
+    let location = _resolve_location(&store, &opts.location)?;
+    for bin_no in [opts.first_bin, opts.second_bin] {
+        if bin_no > location.num_bins {
+            bail!(
+                "bin {} is outside location {}, which only has {} bins",
+                bin_no,
+                location.name,
+                location.num_bins
+            );
+        }
+    }
+    if opts.first_bin == opts.second_bin {
+        bail!("can't swap bin {} with itself", opts.first_bin);
+    }
+
+    let bin_object_ids = |bin_no: i64| -> AHResult<Vec<i64>> {
+        Ok(store
+            .query(
+                Q.equal("type", "item")
+                    .equal("location_id", location.object_id.unwrap())
+                    .equal("bin_no", bin_no),
+            )
+            .iter_converted::<Item>(&store)?
+            .map(|item| item.object_id.unwrap())
+            .collect())
+    };
+    // Both bins are read before either is changed, so the items moved into a bin aren't moved
+    // back out again.
+    let first_object_ids = bin_object_ids(opts.first_bin)?;
+    let second_object_ids = bin_object_ids(opts.second_bin)?;
+
+    let checkpoint = begin_checkpoint(&mut store)?;
+    for (object_ids, bin_no) in [
+        (&first_object_ids, opts.second_bin),
+        (&second_object_ids, opts.first_bin),
+    ] {
+        for object_id in object_ids {
+            checkpoint
+                .query(Item::q().id(*object_id))
+                .set(object!("bin_no" => bin_no))?;
+        }
+    }
+    commit(
+        checkpoint,
+        format!(
+            "swap bins {} and {} of {}",
+            opts.first_bin, opts.second_bin, location.name
+        ),
+    )?;
+
+    for (object_ids, from, to) in [
+        (&first_object_ids, opts.first_bin, opts.second_bin),
+        (&second_object_ids, opts.second_bin, opts.first_bin),
+    ] {
+        println!(
+            "Moved {} item{} from {}/{} to {}/{}",
+            object_ids.len(),
+            if object_ids.len() == 1 { "" } else { "s" },
+            location.name,
+            from,
+            location.name,
+            to
+        );
+    }
+
+    Ok(())
+}
+
+#[derive(Args)]
+struct DumpOpts {
+    #[clap(flatten)]
+    common: CommonOpts,
+    #[clap(
+        long,
+        help = "Write one object per line as it is read, instead of a single array"
+    )]
+    jsonl: bool,
+}
+
+impl WithCommonOpts for DumpOpts {
+    fn common_opts(&self) -> &CommonOpts {
+        &self.common
+    }
+}
+
+fn run_dump(opts: DumpOpts) -> AHResult<()> {
+    let store = opts.common.open_store()?;
+
+    if opts.jsonl {
+        let mut stdout = std::io::stdout().lock();
+
+        for object in store.all().iter()? {
+            serde_json::to_writer(&mut stdout, &object)?;
+            writeln!(stdout)?;
+        }
+
+        return Ok(());
+    }
+
+    serde_json::to_writer(std::io::stdout(), &store.all().iter()?.collect::<Vec<_>>())?;
+
+    Ok(())
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ExportFormat {
+    Csv,
+}
+
+#[derive(Args)]
+struct ExportOpts {
+    #[clap(flatten)]
+    common: CommonOpts,
+    #[clap(long, value_enum, default_value = "csv")]
+    format: ExportFormat,
+}
+
+impl WithCommonOpts for ExportOpts {
+    fn common_opts(&self) -> &CommonOpts {
+        &self.common
+    }
+}
+
+fn run_export(opts: ExportOpts) -> AHResult<()> {
+    let store = opts.common.open_store()?;
+
+    let mut items = store
+        .query(Q.equal("type", "item"))
+        .iter_converted::<Item>(&store)?
+        .collect::<Vec<_>>();
+    items.sort_by_cached_key(|item| item.format());
+
+    match opts.format {
+        ExportFormat::Csv => {
+            println!(
+                "{}",
+                csv::format_record(&["location", "bin", "name", "size"])
+            );
+
+            for item in items {
+                println!(
+                    "{}",
+                    csv::format_record(&[
+                        item.location.name,
+                        item.bin_no.to_string(),
+                        item.name,
+                        item.size,
+                    ])
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Args)]
+struct RestoreOpts {
+    #[clap(flatten)]
+    common: CommonOpts,
+    #[clap(help = "A file written by dump")]
+    path: String,
+    #[clap(long, help = "Replace the store's contents even if it isn't empty")]
+    force: bool,
+}
+
+impl WithCommonOpts for RestoreOpts {
+    fn common_opts(&self) -> &CommonOpts {
+        &self.common
+    }
+}
+
+fn run_restore(opts: RestoreOpts) -> AHResult<()> {
+    let mut store = opts.common.open_store()?;
+
+    let objects: Vec<Object> = serde_json::from_str(
+        &std::fs::read_to_string(&opts.path)
+            .with_context(|| format!("failed to read {}", opts.path))?,
+    )
+    .with_context(|| format!("{} is not a dump", opts.path))?;
+
+    if !opts.force && store.all().exists()? {
+        bail!("the store is not empty; use --force to replace its contents");
+    }
+
+    let checkpoint = begin_checkpoint(&mut store)?;
+    checkpoint.query(Q).delete()?;
+
+    // The objects get new IDs, so references between them are filled in once they all exist.
+    let num_objects = objects.len();
+    let mut new_ids = HashMap::new();
+    let mut references = vec![];
+
+    for mut object in objects {
+        let old_id = object.remove("object_id").and_then(|id| id.as_number());
+        let object_references: Object = object
+            .iter()
+            .filter(|(key, _)| is_reference(key))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        object.retain(|key, _| !is_reference(key));
+
+        let new_id = checkpoint.add(object)?;
+        if let Some(old_id) = old_id {
+            new_ids.insert(old_id, new_id);
+        }
+        if !object_references.is_empty() {
+            references.push((new_id, object_references));
+        }
+    }
+
+    for (object_id, mut object_references) in references {
+        for value in object_references.values_mut() {
+            if let Some(new_id) = value.as_number().and_then(|id| new_ids.get(&id)) {
+                *value = (*new_id).into();
+            }
+        }
+
+        checkpoint.query(Q.id(object_id)).set(object_references)?;
+    }
+
+    commit(checkpoint, format!("restore {} objects", num_objects))?;
+
+    println!("Restored {} objects", num_objects);
+
+    Ok(())
+}
+
+#[derive(Args)]
+struct ImportOpts {
+    #[clap(flatten)]
+    common: CommonOpts,
+    #[clap()]
+    path: String,
+}
+
+impl WithCommonOpts for ImportOpts {
+    fn common_opts(&self) -> &CommonOpts {
+        &self.common
+    }
+}
+
+struct ImportRow {
+    location_name: String,
+    bin_no: Option<i64>,
+    name: String,
+    size: ItemSize,
+}
+
+fn _parse_import_row(record: &str) -> AHResult<ImportRow> {
+    let fields = csv::parse_record(record)?;
+
+    if fields.len() != 4 {
+        bail!("expected 4 fields, found {}", fields.len());
+    }
+
+    Ok(ImportRow {
+        location_name: fields[0].clone(),
+        bin_no: match fields[1].trim() {
+            "" => None,
+            bin => Some(parse_item_bin_number(bin)?),
+        },
+        name: fields[2].clone(),
+        size: fields[3].trim().parse()?,
+    })
+}
+
+fn run_import(opts: ImportOpts) -> AHResult<()> {
+    let mut store = opts.common.open_store()?;
+
+    let contents = std::fs::read_to_string(&opts.path)
+        .with_context(|| format!("failed to read {}", opts.path))?;
+
+    let mut rows = vec![];
+    for (line_no, record) in csv::split_records(&contents) {
+        if record.trim().is_empty() || (line_no == 1 && record.trim() == "location,bin,name,size") {
+            continue;
+        }
+
+        rows.push(_parse_import_row(record).with_context(|| format!("line {}", line_no))?);
+    }
+
+    let mut locations = store
+        .query(Q.equal("type", "location"))
+        .iter_as::<Location>()?
+        .collect::<Vec<_>>();
+    let weights = size_weights(&store)?;
+    let mut fullnesses = locations
+        .iter()
+        .map(|l| bin_fullnesses(&store, l.object_id.unwrap(), l.num_bins))
+        .collect::<AHResult<Vec<_>>>()?;
+
+    for row in &rows {
+        let max_bin = rows
+            .iter()
+            .filter(|r| r.location_name.eq_ignore_ascii_case(&row.location_name))
+            .filter_map(|r| r.bin_no)
+            .max();
+
+        match locations
+            .iter()
+            .find(|l| l.name.eq_ignore_ascii_case(&row.location_name))
+        {
+            Some(location) => {
+                if max_bin.is_some_and(|b| b > location.num_bins) {
+                    bail!(
+                        "location {} only has {} bins",
+                        location.name,
+                        location.num_bins
+                    );
+                }
+            }
+            None => {
+                let num_bins = max_bin.unwrap_or(1).max(1);
+
+                locations.push(Location {
+                    object_id: None,
+                    name: row.location_name.clone(),
+                    num_bins,
+                    rest: Object::new(),
+                });
+                fullnesses.push((1..=num_bins).map(|bin_no| (bin_no, 0)).collect());
+            }
+        }
+    }
+
+    let checkpoint = begin_checkpoint(&mut store)?;
+
+    for location in locations.iter_mut().filter(|l| l.object_id.is_none()) {
+        checkpoint.add_with_id(location)?;
+    }
+
+    let mut items = vec![];
+    for row in rows {
+        let i = locations
+            .iter()
+            .position(|l| l.name.eq_ignore_ascii_case(&row.location_name))
+            .unwrap();
+        let location = &locations[i];
+
+        let mut item = Item {
+            object_id: None,
+            name: row.name,
+            location: location.clone(),
+            bin_no: row
+                .bin_no
+                .unwrap_or_else(|| emptiest_bin(&fullnesses[i], location.num_bins)),
+            size: row.size.to_string(),
+            rest: Object::new(),
+        };
+        item.set_created_now();
+        if let Some(fullness) = fullnesses[i].get_mut(&item.bin_no) {
+            *fullness += item_fullness(&weights, &item)?;
+        }
+
+        checkpoint.add_with_id(&mut item)?;
+        items.push(item);
+    }
+
+    commit(checkpoint, format!("import {} items", items.len()))?;
+
+    for item in items {
+        println!("{}", item.format());
+    }
+
+    Ok(())
+}
+
+fn _format_items(
+    store: &Store,
+    items: &qualia::Collection,
+) -> AHResult<impl Iterator<Item = impl std::fmt::Display>> {
+    let mut formatted_items = items
+        .iter_converted::<Item>(store)?
+        .map(|item| item.format_with_store(store))
+        .collect::<AHResult<Vec<_>>>()?;
+    formatted_items.sort();
+
+    Ok(align_formatted_items(&formatted_items).into_iter())
+}
+
+#[derive(Clone, Copy, Debug, Default, clap::ValueEnum)]
+enum ItemsSort {
+    #[default]
+    Location,
+    Name,
+    /// Largest first, counting how many of each item there are.
+    Size,
+    Bin,
+}
+
+#[derive(Args, Debug)]
+struct ItemsOpts {
+    #[clap(flatten)]
+    common: CommonOpts,
+    #[clap()]
+    name_pattern: Option<String>,
+    #[clap(long, help = "Only show items with this tag")]
+    tag: Option<String>,
+    #[clap(
+        long,
+        help = "Only show items of these sizes, like S,X or M+ for M and larger"
+    )]
+    size: Option<SizeFilter>,
+    #[clap(long, value_enum, default_value_t)]
+    sort: ItemsSort,
+    #[clap(long, help = "Reverse the sort order")]
+    reverse: bool,
+    #[clap(long, help = "Print items as a JSON array")]
+    json: bool,
+    #[clap(
+        long,
+        requires = "name_pattern",
+        help = "Only show items with exactly this name, including case, rather than ones whose names contain its words"
+    )]
+    exact: bool,
+    #[clap(
+        long,
+        conflicts_with = "json",
+        help = "Print each item with this template; placeholders are {location}, {bin}, {name}, \
+            {size}, {count} and {tags}"
+    )]
+    format: Option<ItemTemplate>,
+    #[clap(
+        long,
+        conflicts_with_all = ["name_pattern", "tag", "size", "json", "format"],
+        help = "Show which bins of each location have nothing in them, instead of items"
+    )]
+    empty_bins: bool,
+}
+
+impl WithCommonOpts for ItemsOpts {
+    fn common_opts(&self) -> &CommonOpts {
+        &self.common
+    }
+}
+
+fn _print_empty_bins(store: &Store) -> AHResult<()> {
+    let mut locations = store
+        .query(Q.equal("type", "location"))
+        .iter_as::<Location>()?
+        .collect::<Vec<_>>();
+    locations.sort_by(|a, b| a.name.cmp(&b.name));
+
+    for location in locations {
+        let fullnesses = bin_fullnesses(store, location.object_id.unwrap(), location.num_bins)?;
+        let empty_bins: Vec<_> = (1..=location.num_bins)
+            .filter(|bin_no| fullnesses[bin_no] == 0)
+            .map(|bin_no| bin_no.to_string())
+            .collect();
+
+        if empty_bins.is_empty() {
+            continue;
+        }
+
+        if location.num_bins == 1 {
+            println!("{}: empty", location.name);
+        } else {
+            println!("{}: empty bins {}", location.name, empty_bins.join(", "));
+        }
+    }
+
+    Ok(())
+}
+
+fn run_items(opts: ItemsOpts) -> AHResult<()> {
+    let store = opts.common_opts().open_store()?;
+
+    if opts.empty_bins {
+        return _print_empty_bins(&store);
+    }
+
+    let mut query = Q.equal("type", "item");
+
+    if let Some(name_pattern) = opts.name_pattern {
+        query = _name_query(query, &name_pattern, opts.exact);
+    }
+
+    let mut items = store
+        .query(query)
+        .iter_converted::<Item>(&store)?
+        .filter(|item| opts.tag.as_ref().is_none_or(|tag| item.has_tag(tag)))
+        .filter(|item| {
+            opts.size.as_ref().is_none_or(|filter| {
+                item.size
+                    .parse::<ItemSize>()
+                    .is_ok_and(|size| filter.matches(size))
+            })
+        })
+        .collect::<Vec<_>>();
+    items.sort_by_cached_key(|item| item.format());
+
+    let weights = size_weights(&store)?;
+    match opts.sort {
+        ItemsSort::Location => {}
+        ItemsSort::Name => items.sort_by_cached_key(|item| item.name.to_lowercase()),
+        ItemsSort::Size => items.sort_by_cached_key(|item| {
+            std::cmp::Reverse((
+                item_fullness(&weights, item).unwrap_or(0),
+                item.size.parse::<ItemSize>().ok(),
+            ))
+        }),
+        ItemsSort::Bin => items.sort_by_key(|item| item.bin_no),
+    }
+
+    if opts.reverse {
+        items.reverse();
+    }
+
+    if opts.json {
+        serde_json::to_writer(
+            std::io::stdout(),
+            &items.iter().map(|item| item.to_json()).collect::<Vec<_>>(),
+        )?;
+
+        return Ok(());
+    }
+
+    if let Some(template) = opts.format {
+        for item in &items {
+            println!("{}", template.render(item));
+        }
+
+        return Ok(());
+    }
+
+    let formatted_items: Vec<_> = items.iter().map(|item| item.format()).collect();
+    for formatted_item in align_formatted_items(&formatted_items) {
+        println!("{}", formatted_item);
+    }
+
+    Ok(())
+}
+
+#[derive(Args, Debug)]
+struct LabelsOpts {
+    #[clap(flatten)]
+    common: CommonOpts,
+    #[clap()]
+    name_pattern: Option<String>,
+    #[clap(long, default_value_t = 32, value_parser = clap::value_parser!(u16).range(8..), help = "How many characters wide each label is, including its border")]
+    width: u16,
+}
+
+impl WithCommonOpts for LabelsOpts {
+    fn common_opts(&self) -> &CommonOpts {
+        &self.common
+    }
+}
+
+/// Splits text into lines no wider than `width`, breaking words only if they don't fit on a line
+/// by themselves.
+fn _wrap_label_text(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        let mut word = word.to_string();
+
+        while word.chars().count() > width {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            let rest = word.split_off(word.char_indices().nth(width).unwrap().0);
+            lines.push(word);
+            word = rest;
+        }
+
+        if line.is_empty() {
+            line = word;
+        } else if line.chars().count() + 1 + word.chars().count() <= width {
+            line.push(' ');
+            line.push_str(&word);
+        } else {
+            lines.push(std::mem::replace(&mut line, word));
+        }
+    }
+
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
+fn run_labels(opts: LabelsOpts) -> AHResult<()> {
+    let store = opts.common_opts().open_store()?;
+
+    let mut query = Q.equal("type", "item");
+
+    if let Some(name_pattern) = opts.name_pattern {
+        query = query.like("name", &name_pattern);
+    }
+
+    let mut formatted_items = store
+        .query(query)
+        .iter_converted::<Item>(&store)?
+        .map(|item| item.format())
+        .collect::<Vec<_>>();
+    formatted_items.sort();
+
+    let inner_width = opts.width as usize - 4;
+    let border = format!("+{}+", "-".repeat(inner_width + 2));
+
+    for formatted_item in formatted_items {
+        println!("{}", border);
+        for line in _wrap_label_text(&formatted_item.to_string(), inner_width) {
+            println!("| {:inner_width$} |", line, inner_width = inner_width);
+        }
+        println!("{}", border);
+    }
+
+    Ok(())
+}
+
+#[derive(Args, Debug)]
+struct LastOpts {
+    #[clap(flatten)]
+    common: CommonOpts,
+    #[clap(default_value_t = 10)]
+    count: usize,
+}
+
+impl WithCommonOpts for LastOpts {
+    fn common_opts(&self) -> &CommonOpts {
+        &self.common
+    }
+}
+
+fn run_last(opts: LastOpts) -> AHResult<()> {
+    let store = opts.common_opts().open_store()?;
+
+    let mut items = store
+        .query(Q.equal("type", "item"))
+        .iter_converted::<Item>(&store)?
+        .collect::<Vec<_>>();
+    // Items added in the same second are told apart by their IDs, which only go up; items from
+    // before timestamps were recorded go last.
+    items.sort_by_key(|item| std::cmp::Reverse((item.created_at(), item.object_id)));
+    items.truncate(opts.count);
+
+    let formatted_items: Vec<_> = items.iter().map(|item| item.format()).collect();
+    for formatted_item in align_formatted_items(&formatted_items) {
+        println!("{}", formatted_item);
+    }
+
+    Ok(())
+}
+
+#[derive(Args, Debug)]
+struct FindOpts {
+    #[clap(flatten)]
+    common: CommonOpts,
+    #[clap(required = true)]
+    query: Vec<String>,
+}
+
+impl WithCommonOpts for FindOpts {
+    fn common_opts(&self) -> &CommonOpts {
+        &self.common
+    }
+}
+
+fn run_find(opts: FindOpts) -> AHResult<()> {
+    let store = opts.common_opts().open_store()?;
+    let matcher = SkimMatcherV2::default();
+
+    let query_words: Vec<_> = opts
+        .query
+        .iter()
+        .flat_map(|q| q.split_whitespace())
+        .collect();
+
+    let mut formatted_items = store
+        .query(Q.equal("type", "item"))
+        .iter_converted::<Item>(&store)?
+        .map(|item| item.format_with_store(&store))
+        .collect::<AHResult<Vec<_>>>()?;
+    formatted_items.sort();
+
+    // Each word of the query has to match somewhere, so `bat kitchen` finds batteries in the
+    // kitchen no matter which order the words were typed in.
+    let mut scored_items: Vec<_> = formatted_items
+        .into_iter()
+        .filter_map(|formatted_item| {
+            let haystack = format!(
+                "{} {} {}",
+                formatted_item.format_location(),
+                formatted_item.size,
+                formatted_item.name
+            );
+
+            query_words
+                .iter()
+                .map(|word| matcher.fuzzy_match(&haystack, word))
+                .sum::<Option<i64>>()
+                .map(|score| (score, formatted_item))
+        })
+        .collect();
+    scored_items.sort_by_key(|(score, _)| -score);
+
+    let formatted_items: Vec<_> = scored_items.into_iter().map(|(_, item)| item).collect();
+    for formatted_item in align_formatted_items(&formatted_items) {
+        println!("{}", formatted_item);
+    }
+
+    Ok(())
+}
+
+#[derive(Args)]
+struct DeleteOpts {
+    #[clap(flatten)]
+    common: CommonOpts,
+    #[clap(short, long)]
+    all: bool,
+    #[clap(
+        short,
+        long,
+        help = "Delete multiple items without asking first (required when not run from a terminal)"
+    )]
+    yes: bool,
+    #[clap(
+        long,
+        conflicts_with = "name_pattern",
+        help = "Delete the item with this object ID (as shown by dump)"
+    )]
+    id: Option<i64>,
+    #[clap(
+        long,
+        requires = "name_pattern",
+        help = "Only delete items with exactly this name, including case, rather than ones whose names contain its words"
+    )]
+    exact: bool,
+    #[clap(required_unless_present = "id")]
+    name_pattern: Option<String>,
+}
+
+impl WithCommonOpts for DeleteOpts {
+    fn common_opts(&self) -> &CommonOpts {
+        &self.common
+    }
+}
+
+fn run_delete(opts: DeleteOpts) -> AHResult<()> {
+    let mut store = opts.common.open_store()?;
+
+    let checkpoint = begin_checkpoint(&mut store)?;
+
+    let (matching_items, description) = match (opts.id, opts.name_pattern) {
+        (Some(id), _) => (
+            checkpoint.query(Item::q().id(id)),
+            format!("delete item {}", id),
+        ),
+        (None, Some(name_pattern)) => (
+            checkpoint.query(_name_query(
+                Q.equal("type", "item"),
+                &name_pattern,
+                opts.exact,
+            )),
+            format!("delete items matching {}", name_pattern),
+        ),
+        (None, None) => unreachable!(),
+    };
+
+    if let Some(id) = opts.id {
+        if matching_items.len()? == 0 {
+            bail!(Failure::NotFound(format!("no item with ID {}", id)));
+        }
+    }
+
+    if matching_items.len()? > 1 && !opts.all {
+        let formatted_items: Vec<_> = _format_items(&checkpoint, &matching_items)?
+            .map(|item| format!("    {}", item))
+            .collect();
+
+        bail!(Failure::Ambiguous(format!(
+            "found multiple matching items (use --all to delete multiple items):\n{}",
+            formatted_items.join("\n")
+        )));
+    }
+
+    let num_items = matching_items.len()?;
+    log_verbose(1, format!("query: found {} items to delete", num_items));
+
+    if num_items > 1 && !opts.yes {
+        if !std::io::stdin().is_terminal() {
+            bail!(Failure::Ambiguous(format!(
+                "found {} matching items; use --yes to delete them without a terminal",
+                num_items
+            )));
+        }
+
+        for formatted_item in _format_items(&checkpoint, &matching_items)? {
+            println!("    {}", formatted_item);
+        }
+        print!("Delete these {} items? [y/N] ", num_items);
+        std::io::stdout().flush()?;
+
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+
+        if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+            println!("Nothing deleted");
+            return Ok(());
+        }
+    }
+
+    if !opts.common.quiet {
+        for formatted_item in _format_items(&checkpoint, &matching_items)? {
+            println!("Deleted {}", formatted_item);
+        }
+    }
+
+    matching_items.delete()?;
+
+    commit(checkpoint, description)?;
+
+    Ok(())
+}
+
+/// The items matching the name pattern, failing if there are none or if there are several and
+/// `all` isn't set.
+fn _matching_items(
+    store: &Store,
+    name_pattern: &str,
+    all: bool,
+    action: &str,
+) -> AHResult<Vec<Item>> {
+    let matching_items = store.query(Q.equal("type", "item").like("name", name_pattern));
+
+    if matching_items.len()? == 0 {
+        bail!(Failure::NotFound(format!(
+            "no items matching \"{}\"",
+            name_pattern
+        )));
+    }
+
+    if matching_items.len()? > 1 && !all {
+        let formatted_items: Vec<_> = _format_items(store, &matching_items)?
+            .map(|item| format!("    {}", item))
+            .collect();
+
+        bail!(Failure::Ambiguous(format!(
+            "found multiple matching items (use --all to {} multiple items):\n{}",
+            action,
+            formatted_items.join("\n")
+        )));
+    }
+
+    Ok(matching_items.iter_converted::<Item>(store)?.collect())
+}
+
+#[derive(Args)]
+struct MoveOpts {
+    #[clap(flatten)]
+    common: CommonOpts,
+    #[clap(short, long)]
+    all: bool,
+    #[clap()]
+    name_pattern: String,
+    #[clap()]
+    location: ItemLocation,
+}
+
+impl WithCommonOpts for MoveOpts {
+    fn common_opts(&self) -> &CommonOpts {
+        &self.common
+    }
+}
+
+fn run_move(opts: MoveOpts) -> AHResult<()> {
+    let mut store = opts.common.open_store()?;
+
+    if opts.location.bin_range.is_some() {
+        bail!("bin ranges can only be used when adding items");
+    }
+
+    let location = _resolve_location(&store, &opts.location.location)?;
+    if let Some(bin_no) = opts.location.bin {
+        if bin_no > location.num_bins {
+            bail!(
+                "location {} only has {} bins",
+                location.name,
+                location.num_bins
+            );
+        }
+    }
+
+    let mut items = _matching_items(&store, &opts.name_pattern, opts.all, "move")?;
+    let weights = size_weights(&store)?;
+    let mut fullnesses = bin_fullnesses(&store, location.object_id.unwrap(), location.num_bins)?;
+
+    let checkpoint = begin_checkpoint(&mut store)?;
+
+    for item in &mut items {
+        // Items already in the location shouldn't count against the bins they're leaving.
+        if item.location.object_id == location.object_id {
+            if let Some(fullness) = fullnesses.get_mut(&item.bin_no) {
+                *fullness -= item_fullness(&weights, item)?;
+            }
+        }
+
+        let bin_no = opts
+            .location
+            .bin
+            .unwrap_or_else(|| emptiest_bin(&fullnesses, location.num_bins));
+        if let Some(fullness) = fullnesses.get_mut(&bin_no) {
+            *fullness += item_fullness(&weights, item)?;
+        }
+
+        checkpoint
+            .query(Item::q().id(item.object_id.unwrap()))
+            .set(object!(
+                "location_id" => location.object_id.unwrap(),
+                "bin_no" => bin_no,
+            ))?;
+
+        item.location = location.clone();
+        item.bin_no = bin_no;
+    }
+
+    commit(
+        checkpoint,
+        format!(
+            "move items matching {} to {}",
+            opts.name_pattern, location.name
+        ),
+    )?;
+
+    let mut formatted_items = items
+        .iter()
+        .map(|item| item.format_with_store(&store))
+        .collect::<AHResult<Vec<_>>>()?;
+    formatted_items.sort();
+
+    for formatted_item in align_formatted_items(&formatted_items) {
+        println!("Moved {}", formatted_item);
+    }
+
+    Ok(())
+}
+
+#[derive(Args)]
+struct TagOpts {
+    #[clap(flatten)]
+    common: CommonOpts,
+    #[clap(short, long)]
+    all: bool,
+    #[clap()]
+    name_pattern: String,
+    #[clap(required = true, value_parser = tag_value_parser)]
+    tags: Vec<String>,
+}
+
+impl WithCommonOpts for TagOpts {
+    fn common_opts(&self) -> &CommonOpts {
+        &self.common
+    }
+}
+
+fn _change_tags(opts: TagOpts, adding: bool) -> AHResult<()> {
+    let mut store = opts.common.open_store()?;
+
+    let action = if adding { "tag" } else { "untag" };
+    let mut items = _matching_items(&store, &opts.name_pattern, opts.all, action)?;
+
+    let checkpoint = begin_checkpoint(&mut store)?;
+
+    for item in &mut items {
+        let mut tags = item.tags();
+        tags.retain(|t| !opts.tags.iter().any(|tag| t.eq_ignore_ascii_case(tag)));
+        if adding {
+            tags.extend(opts.tags.iter().cloned());
+        }
+        item.set_tags(&tags);
+
+        checkpoint
+            .query(Item::q().id(item.object_id.unwrap()))
+            .set(object!("tags" => item.rest["tags"].clone()))?;
+    }
+
+    commit(
+        checkpoint,
+        format!(
+            "{} items matching {} with {}",
+            action,
+            opts.name_pattern,
+            opts.tags.join(", ")
+        ),
+    )?;
+
+    items.sort_by_cached_key(|item| item.format());
+    let formatted_items: Vec<_> = items.iter().map(|item| item.format()).collect();
+
+    for (formatted_item, item) in align_formatted_items(&formatted_items)
+        .into_iter()
+        .zip(&items)
+    {
+        println!("{} [{}]", formatted_item, item.tags().join(", "));
+    }
+
+    Ok(())
+}
+
+/// A map of which of the location's bins have anything in them, like `[X..X]`.
+fn _occupancy_map(store: &Store, location: &Location) -> AHResult<String> {
+    let fullnesses = bin_fullnesses(store, location.object_id.unwrap(), location.num_bins)?;
+
+    Ok(format!(
+        "[{}]",
+        (1..=location.num_bins)
+            .map(|bin_no| if fullnesses[&bin_no] > 0 { 'X' } else { '.' })
+            .collect::<String>()
+    ))
+}
+
+/// Prints the location and, indented under it, its children. If given a store, multi-bin
+/// locations also show which of their bins are occupied.
+fn _print_location_tree(
+    locations: &[Location],
+    location: &Location,
+    depth: usize,
+    occupancy_store: Option<&Store>,
+) -> AHResult<()> {
+    let alias = match location.alias() {
+        Some(alias) => format!(" [{}]", alias),
+        None => "".to_string(),
+    };
+    let indent = "    ".repeat(depth);
+
+    if location.num_bins > 1 {
+        let occupancy = match occupancy_store {
+            Some(store) => format!(" {}", _occupancy_map(store, location)?),
+            None => "".to_string(),
+        };
+
+        println!(
+            "{}{}{} ({} bins){}",
+            indent, location.name, alias, location.num_bins, occupancy
+        );
+    } else {
+        println!("{}{}{}", indent, location.name, alias);
+    }
+
+    for child in locations
+        .iter()
+        .filter(|l| l.parent_id().is_some() && l.parent_id() == location.object_id)
+    {
+        _print_location_tree(locations, child, depth + 1, occupancy_store)?;
+    }
+
+    Ok(())
+}
+
+#[derive(Args)]
+struct LocationsOpts {
+    #[clap(flatten)]
+    common: CommonOpts,
+    #[clap(
+        long,
+        help = "Print locations as a JSON array, including how full each bin is"
+    )]
+    json: bool,
+    #[clap(
+        long,
+        conflicts_with = "json",
+        help = "Show which bins of each location have items in them, like [X..X]"
+    )]
+    occupancy: bool,
+}
+
+impl WithCommonOpts for LocationsOpts {
+    fn common_opts(&self) -> &CommonOpts {
+        &self.common
+    }
+}
+
+fn run_locations(opts: LocationsOpts) -> AHResult<()> {
+    let store = opts.common.open_store()?;
+
+    let mut locations = store
+        .query(Q.equal("type", "location"))
+        .iter_as::<Location>()?
+        .collect::<Vec<_>>();
+
+    if opts.json {
+        locations.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let locations_json = locations
+            .iter()
+            .map(|location| -> AHResult<serde_json::Value> {
+                let fullnesses =
+                    bin_fullnesses(&store, location.object_id.unwrap(), location.num_bins)?;
+
+                Ok(serde_json::json!({
+                    "object_id": location.object_id,
+                    "name": location.name,
+                    "num_bins": location.num_bins,
+                    "bins": (1..=location.num_bins)
+                        .map(|bin_no| fullnesses[&bin_no])
+                        .collect::<Vec<_>>(),
+                }))
+            })
+            .collect::<AHResult<Vec<_>>>()?;
+        serde_json::to_writer(std::io::stdout(), &locations_json)?;
+
+        return Ok(());
+    }
+
+    // Locations whose parent has since been removed are shown at the top level.
+    for location in locations.iter().filter(|l| {
+        l.parent_id()
+            .is_none_or(|parent_id| !locations.iter().any(|p| p.object_id == Some(parent_id)))
+    }) {
+        _print_location_tree(&locations, location, 0, opts.occupancy.then_some(&store))?;
+    }
+
+    Ok(())
+}
+
+#[derive(Args)]
+struct QuickaddOpts {
+    #[clap(flatten)]
+    common: CommonOpts,
+    #[clap()]
+    location: ItemLocation,
+    #[clap(long, help = "Show where each item would go without adding it")]
+    dry_run: bool,
+    #[clap(
+        long,
+        help = "Read item names from this file instead of prompting (also done when stdin is not a terminal)"
+    )]
+    from_file: Option<String>,
+    #[clap(
+        long,
+        value_enum,
+        ignore_case = true,
+        default_value = "S",
+        help = "Size of items whose lines don't give one"
+    )]
+    default_size: ItemSize,
+    #[clap(
+        long,
+        help = "Prompt with this instead of the location and bins, like \"tools> \""
+    )]
+    prompt: Option<String>,
+}
+
+fn run_quickadd(opts: QuickaddOpts) -> AHResult<()> {
+    let mut store = opts.common.open_store()?;
+
+    let location = _resolve_location(&store, &opts.location.location)?;
+
+    let bins = opts.location.bins_in(&location)?;
+    let bin_number_display = match (opts.location.bin, opts.location.bin_range) {
+        (Some(bin_no), _) => format!("/{}", bin_no),
+        (None, Some((start, end))) => format!("/{}-{}", start, end),
+        (None, None) => "".to_string(),
+    };
+    let prompt = opts
+        .prompt
+        .clone()
+        .unwrap_or_else(|| location.name.clone() + &bin_number_display + "> ");
+
+    let lines: Option<Box<dyn BufRead>> = match &opts.from_file {
+        Some(path) => Some(Box::new(BufReader::new(
+            File::open(path).with_context(|| format!("failed to open {}", path))?,
+        ))),
+        None if !std::io::stdin().is_terminal() => Some(Box::new(std::io::stdin().lock())),
+        None => None,
+    };
+
+    let bin_prefix_re = regex::Regex::new(r"^(\d+)\s+(.+)$")?;
+    let size_suffix_re = regex::Regex::new(r"^(.*?)\s+([SMLX])$")?;
+    let size_prefix_re = regex::Regex::new(r"^([SMLX])\s+(.+)$")?;
+
+    // Only interactive sessions get a running count, to keep piped output to one line per item.
+    let show_count = lines.is_none();
+    let mut added = 0;
+    let mut redo_stack = RedoStack::load(opts.common.redo_log_path()?)?;
+
+    let mut add_line = |line: &str| -> AHResult<()> {
+        let mut line = line.trim();
+
+        if line == "undo" || line == "\\u" {
+            // Only this session's additions can be undone from here.
+            if added == 0 {
+                println!("Nothing to undo");
+                return Ok(());
+            }
+
+            if let Some(description) = redo_stack.undo(&mut store)? {
+                added -= 1;
+                println!("Undid: {} ({} added)", description, added);
+            }
+
+            return redo_stack.save(opts.common.redo_log_path()?);
+        }
+
+        let mut bin_no = opts.location.bin;
+
+        if let Some(cap) = bin_prefix_re.captures(line) {
+            bin_no = Some(parse_item_bin_number(&cap[1])?);
+            line = cap.get(2).unwrap().as_str();
+        }
+
+        if bin_no.is_none() && opts.location.bin_range.is_some() {
+            bin_no = Some(choose_bin_in(
+                &store,
+                &location,
+                bins.clone(),
+                FillOrder::default(),
+            )?);
+        }
+
+        let mut name = line.to_string();
+        let mut size = opts.default_size;
+
+        // Both need a name besides the size, so an item just called "M" keeps its name.
+        if let Some(cap) = size_suffix_re.captures(line) {
+            name = cap[1].to_string();
+            size = cap[2].parse()?;
+        } else if let Some(cap) = size_prefix_re.captures(line) {
+            size = cap[1].parse()?;
+            name = cap[2].to_string();
+        }
+
+        let mut item = prepare_item(
+            &store,
+            name.to_string(),
+            &location,
+            bin_no,
+            size,
+            1,
+            CapacityCheck::Warn,
+        )?;
+
+        _print_added_item(
+            &mut store,
+            &mut item,
+            opts.dry_run,
+            FillOrder::default(),
+            opts.common.quiet,
+        )?;
+
+        if !opts.dry_run {
+            added += 1;
+
+            if show_count && !opts.common.quiet {
+                println!("({} added)", added);
+            }
+        }
+
+        Ok(())
+    };
+
+    if let Some(lines) = lines {
+        for line in lines.lines() {
+            let line = line?;
+
+            if !line.trim().is_empty() {
+                add_line(&line)?;
+            }
+        }
+    } else {
+        let mut rl = Editor::<()>::new()?;
+
+        while let Ok(line) = rl.readline(&prompt) {
+            // A typo shouldn't end the whole session.
+            if let Err(e) = add_line(&line) {
+                println!("Error: {}", e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Args)]
+struct StatsOpts {
+    #[clap(flatten)]
+    common: CommonOpts,
+    #[clap(long, help = "Only list bins that are nearly full, fullest first")]
+    near_full: bool,
+    #[clap(
+        long,
+        requires = "near_full",
+        default_value = "80%",
+        value_parser = _parse_percentage,
+        help = "How full a bin must be to count as nearly full, compared to its location's capacity or else its fullest bin"
+    )]
+    threshold: i64,
+}
+
+impl WithCommonOpts for StatsOpts {
+    fn common_opts(&self) -> &CommonOpts {
+        &self.common
+    }
+}
+
+fn _parse_percentage(s: &str) -> Result<i64, String> {
+    s.strip_suffix('%')
+        .unwrap_or(s)
+        .parse::<i64>()
+        .ok()
+        .filter(|p| (1..=100).contains(p))
+        .ok_or_else(|| "must be a percentage from 1% to 100%".to_string())
+}
+
+/// Lists bins at least `threshold` percent full, compared to the location's capacity if it has
+/// one, or else to its fullest bin. Locations with one bin and no capacity have nothing to compare
+/// to, so are left out.
+fn _print_near_full_bins(store: &Store, locations: &[Location], threshold: i64) -> AHResult<()> {
+    let mut near_full_bins = vec![];
+
+    for location in locations {
+        let fullnesses = bin_fullnesses(store, location.object_id.unwrap(), location.num_bins)?;
+        let (limit, compared_to) = match location.capacity() {
+            Some(capacity) => (capacity, format!("capacity {}", capacity)),
+            None if location.num_bins > 1 => (
+                fullnesses.values().copied().max().unwrap_or(0),
+                "the fullest bin".to_string(),
+            ),
+            None => continue,
+        };
+
+        if limit == 0 {
+            continue;
+        }
+
+        for (bin_no, fullness) in fullnesses {
+            if fullness > 0 && fullness * 100 >= limit * threshold {
+                near_full_bins.push((fullness, location, bin_no, limit, compared_to.clone()));
+            }
+        }
+    }
+
+    near_full_bins.sort_by(|a, b| {
+        b.0.cmp(&a.0)
+            .then_with(|| a.1.name.cmp(&b.1.name))
+            .then(a.2.cmp(&b.2))
+    });
+
+    for (fullness, location, bin_no, limit, compared_to) in near_full_bins {
+        println!(
+            "{}/{}: {} ({}% of {})",
+            location.name,
+            bin_no,
+            fullness,
+            fullness * 100 / limit,
+            compared_to
+        );
+    }
+
+    Ok(())
+}
+
+fn run_stats(opts: StatsOpts) -> AHResult<()> {
+    let store = opts.common.open_store()?;
+
+    let mut locations = store
+        .query(Q.equal("type", "location"))
+        .iter_as::<Location>()?
+        .collect::<Vec<_>>();
+    locations.sort_by(|a, b| a.name.cmp(&b.name));
+
+    if opts.near_full {
+        return _print_near_full_bins(&store, &locations, opts.threshold);
+    }
+
+    for location in locations {
+        let item_count = store
+            .query(
+                Q.equal("type", "item")
+                    .equal("location_id", location.object_id.unwrap()),
+            )
+            .len()?;
+        let fullnesses = bin_fullnesses(&store, location.object_id.unwrap(), location.num_bins)?;
+
+        println!(
+            "{}: {} item{}, fullness {}",
+            location.name,
+            item_count,
+            if item_count == 1 { "" } else { "s" },
+            fullnesses.values().sum::<i64>(),
+        );
+
+        if location.num_bins > 1 {
+            for bin_no in 1..=location.num_bins {
+                println!("    {}/{}: {}", location.name, bin_no, fullnesses[&bin_no]);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn run_redo(opts: CommonOpts) -> AHResult<()> {
+    let mut store = opts.open_store()?;
+    let mut redo_stack = RedoStack::load(opts.redo_log_path()?)?;
+
+    match redo_stack.redo(&mut store)? {
+        Some(description) => println!("Redid: {}", description),
+        None => println!("Nothing to redo"),
+    }
+
+    redo_stack.save(opts.redo_log_path()?)
+}
+
+#[derive(Args)]
+struct UndoOpts {
+    #[clap(flatten)]
+    common: CommonOpts,
+    #[clap(
+        long,
+        value_name = "N",
+        num_args = 0..=1,
+        default_missing_value = "10",
+        help = "Show the last N actions (default 10) instead of undoing"
+    )]
+    list: Option<usize>,
+}
+
+impl WithCommonOpts for UndoOpts {
+    fn common_opts(&self) -> &CommonOpts {
+        &self.common
+    }
+}
+
+#[derive(Args)]
+struct CompactOpts {
+    #[clap(flatten)]
+    common: CommonOpts,
+    #[clap(
+        long,
+        value_name = "N",
+        default_value_t = 10,
+        help = "How many of the most recently undone actions to keep for redo"
+    )]
+    keep_redo: usize,
+}
+
+impl WithCommonOpts for CompactOpts {
+    fn common_opts(&self) -> &CommonOpts {
+        &self.common
+    }
+}
+
+fn _file_size(path: impl AsRef<std::path::Path>) -> u64 {
+    std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+fn run_compact(opts: CompactOpts) -> AHResult<()> {
+    let common = &opts.common;
+    set_verbosity(common.verbose);
+    set_read_only(common.read_only);
+    check_writable()?;
+
+    let store_path = common.resolved_store_path()?;
+    if !std::path::Path::new(&store_path).exists() {
+        bail!(Failure::Store(format!(
+            "store {} does not exist",
+            store_path
+        )));
+    }
+
+    let redo_log_path = common.redo_log_path()?;
+    let store_files = [
+        store_path.clone(),
+        format!("{}-wal", store_path),
+        redo_log_path.clone(),
+    ];
+    let size_before: u64 = store_files.iter().map(_file_size).sum();
+
+    compact_store(&store_path, || {
+        let mut redo_stack = RedoStack::load(&redo_log_path)?;
+        let forgotten = redo_stack.forget_oldest(opts.keep_redo);
+
+        if forgotten > 0 {
+            redo_stack.save(&redo_log_path)?;
+            println!("Forgot {} undone action(s)", forgotten);
+        }
+
+        Ok(())
+    })?;
+
+    let size_after: u64 = store_files.iter().map(_file_size).sum();
+    println!("Reclaimed {} bytes", size_before.saturating_sub(size_after));
+
+    Ok(())
+}
+
+fn run_history(opts: CommonOpts) -> AHResult<()> {
+    // Opening the store first creates it if needed, so there is a checkpoints table to read.
+    opts.open_store()?;
+
+    for (timestamp, description) in checkpoint_log(opts.resolved_store_path()?)? {
+        println!("{} UTC: {}", timestamp, description);
+    }
+
+    Ok(())
+}
+
+fn run_undo(opts: UndoOpts) -> AHResult<()> {
+    let common = &opts.common;
+    let mut store = common.open_store()?;
+
+    if let Some(limit) = opts.list {
+        for description in recent_checkpoint_descriptions(common.resolved_store_path()?, limit)? {
+            println!("{}", description);
+        }
+
+        return Ok(());
+    }
+
+    let mut redo_stack = RedoStack::load(common.redo_log_path()?)?;
+
+    match redo_stack.undo(&mut store)? {
+        Some(description) => println!("Undid: {}", description),
+        None => println!("Nothing to undo"),
+    }
+
+    redo_stack.save(common.redo_log_path()?)
+}
+
+fn main() {
+    if let Err(e) = Opts::parse().subcmd.invoke() {
+        eprintln!("Error: {:?}", e);
+        std::process::exit(e.downcast_ref::<Failure>().map_or(1, Failure::exit_code));
+    }
 }
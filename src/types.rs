@@ -1,6 +1,7 @@
 use anyhow::{anyhow, bail, Context};
 use clap::ValueEnum;
-use qualia::{object, Object, ObjectShape, ObjectShapeWithId, Queryable, Store};
+use qualia::{Object, ObjectShape, ObjectShapeWithId, Queryable, Store};
+use std::ops::RangeInclusive;
 use std::str::FromStr;
 
 use crate::AHResult;
@@ -11,6 +12,34 @@ pub struct Location {
     pub object_id: Option<i64>,
     pub name: String,
     pub num_bins: i64,
+
+    #[rest_fields]
+    pub rest: Object,
+}
+
+impl Location {
+    /// The location this one is grouped under, like a room holding several shelves.
+    pub fn parent_id(&self) -> Option<i64> {
+        self.rest.get("parent_id").and_then(|p| p.as_number())
+    }
+
+    /// A short code that can be used in place of the location's name.
+    pub fn alias(&self) -> Option<&str> {
+        self.rest
+            .get("alias")
+            .and_then(|a| a.as_str())
+            .map(|a| a.as_str())
+    }
+
+    /// How much each bin can hold, in the same units as item sizes.
+    pub fn capacity(&self) -> Option<i64> {
+        self.rest.get("capacity").and_then(|c| c.as_number())
+    }
+
+    /// The bin most recently chosen for an item added with the round-robin fill order.
+    pub fn last_bin(&self) -> Option<i64> {
+        self.rest.get("last_bin").and_then(|b| b.as_number())
+    }
 }
 
 #[derive(Clone, Debug, ObjectShape, PartialEq, Eq)]
@@ -27,8 +56,70 @@ pub struct Item {
 }
 
 impl Item {
+    /// How many of this item are stored together. Items without an explicit count are single.
+    pub fn count(&self) -> i64 {
+        self.rest
+            .get("count")
+            .and_then(|c| c.as_number())
+            .unwrap_or(1)
+    }
+
+    pub fn set_count(&mut self, count: i64) {
+        if count == 1 {
+            self.rest.remove("count");
+        } else {
+            self.rest.insert("count".into(), count.into());
+        }
+    }
+
+    /// Labels for grouping items across locations, kept as a comma-separated list.
+    pub fn tags(&self) -> Vec<String> {
+        self.rest
+            .get("tags")
+            .and_then(|t| t.as_str())
+            .map(|t| {
+                t.split(',')
+                    .filter(|t| !t.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn set_tags(&mut self, tags: &[String]) {
+        self.rest.insert("tags".into(), tags.join(",").into());
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags().iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// When the item was added, in seconds since the Unix epoch. Items added before this was
+    /// recorded don't have one.
+    pub fn created_at(&self) -> Option<i64> {
+        self.rest.get("created_at").and_then(|c| c.as_number())
+    }
+
+    /// Records the current time as when the item was added.
+    pub fn set_created_now(&mut self) {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+
+        self.rest.insert("created_at".into(), now.into());
+    }
+
+    /// How much room the item takes up, if given explicitly instead of by its size.
+    pub fn volume(&self) -> Option<i64> {
+        self.rest.get("volume").and_then(|v| v.as_number())
+    }
+
+    pub fn set_volume(&mut self, volume: i64) {
+        self.rest.insert("volume".into(), volume.into());
+    }
+
     pub fn format(&self) -> FormattedItem {
-        let bin_no = if self.location.num_bins > 1 {
+        let bin_no = if self.location.num_bins > 1 || self.bin_no == OVERFLOW_BIN {
             Some(self.bin_no)
         } else {
             None
@@ -38,26 +129,55 @@ impl Item {
             location_name: self.location.name.clone(),
             bin_no,
             name: self.name.clone(),
-            size: self.size.clone(),
+            size: match self.volume() {
+                Some(volume) => format!("{}u", volume),
+                None => self.size.clone(),
+            },
+            count: self.count(),
         }
     }
 
     pub fn format_with_store(&self, _store: &Store) -> AHResult<FormattedItem> {
         Ok(self.format())
     }
+
+    /// A flattened view of the item for scripts, with the location referred to by name.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut json = serde_json::json!({
+            "object_id": self.object_id,
+            "location": self.location.name,
+            "bin_no": self.bin_no,
+            "name": self.name,
+            "size": self.size,
+            "count": self.count(),
+        });
+
+        if let Some(volume) = self.volume() {
+            json["volume"] = volume.into();
+        }
+
+        json
+    }
 }
 
+/// The bin for items that don't fit in, or are on their way to, any of a location's real bins. It
+/// is never chosen automatically.
+pub const OVERFLOW_BIN: i64 = 0;
+
 #[derive(Ord, PartialOrd, Eq, PartialEq)]
 pub struct FormattedItem {
     pub location_name: String,
     pub bin_no: Option<i64>,
     pub name: String,
     pub size: String,
+    pub count: i64,
 }
 
 impl FormattedItem {
     pub fn format_location(&self) -> String {
-        if let Some(bin_no) = self.bin_no {
+        if self.bin_no == Some(OVERFLOW_BIN) {
+            format!("{} (overflow)", self.location_name)
+        } else if let Some(bin_no) = self.bin_no {
             format!("{}/{}", self.location_name, bin_no)
         } else {
             self.location_name.clone()
@@ -65,15 +185,130 @@ impl FormattedItem {
     }
 }
 
+/// Pads the location (and its colon) to the width given in the format string, if any, so that
+/// names line up.
 impl std::fmt::Display for FormattedItem {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
         write!(
             f,
-            "{}: {} ({})",
-            self.format_location(),
+            "{:width$} {} ({}",
+            format!("{}:", self.format_location()),
             self.name,
-            self.size
-        )
+            self.size,
+            width = f.width().unwrap_or(0),
+        )?;
+
+        if self.count > 1 {
+            write!(f, " x{}", self.count)?;
+        }
+
+        write!(f, ")")
+    }
+}
+
+/// Formats a list of items with their names lined up.
+pub fn align_formatted_items(items: &[FormattedItem]) -> Vec<String> {
+    let width = items
+        .iter()
+        .map(|item| item.format_location().chars().count() + 1)
+        .max()
+        .unwrap_or(0);
+
+    items
+        .iter()
+        .map(|item| format!("{:width$}", item, width = width))
+        .collect()
+}
+
+/// A part of an item template; see `ItemTemplate`.
+#[derive(Clone, Debug, PartialEq)]
+enum TemplatePart {
+    Text(String),
+    Location,
+    Bin,
+    Name,
+    Size,
+    Count,
+    Tags,
+}
+
+/// A line of text to print for each item, like `{location}/{bin} {name} [{size}]`. `{{` and `}}`
+/// stand for literal braces.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ItemTemplate {
+    parts: Vec<TemplatePart>,
+}
+
+impl ItemTemplate {
+    pub fn render(&self, item: &Item) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                TemplatePart::Text(text) => text.clone(),
+                TemplatePart::Location => item.location.name.clone(),
+                TemplatePart::Bin => item.bin_no.to_string(),
+                TemplatePart::Name => item.name.clone(),
+                TemplatePart::Size => item.format().size,
+                TemplatePart::Count => item.count().to_string(),
+                TemplatePart::Tags => item.tags().join(","),
+            })
+            .collect()
+    }
+}
+
+impl FromStr for ItemTemplate {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> AHResult<Self> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut field = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => field.push(c),
+                            None => bail!("unclosed {{ in format"),
+                        }
+                    }
+
+                    if !text.is_empty() {
+                        parts.push(TemplatePart::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(match field.as_str() {
+                        "location" => TemplatePart::Location,
+                        "bin" => TemplatePart::Bin,
+                        "name" => TemplatePart::Name,
+                        "size" => TemplatePart::Size,
+                        "count" => TemplatePart::Count,
+                        "tags" => TemplatePart::Tags,
+                        _ => bail!(
+                            "unknown placeholder {{{}}}; expected one of {{location}}, {{bin}}, {{name}}, {{size}}, {{count}} or {{tags}}",
+                            field
+                        ),
+                    });
+                }
+                '}' => bail!("unmatched }} in format; use }}}} for a literal brace"),
+                c => text.push(c),
+            }
+        }
+
+        if !text.is_empty() {
+            parts.push(TemplatePart::Text(text));
+        }
+
+        Ok(Self { parts })
     }
 }
 
@@ -89,14 +324,73 @@ pub fn parse_bin_number(s: &str) -> AHResult<i64> {
         })
 }
 
+/// Like `parse_bin_number`, but also allows the overflow bin, for where an item is rather than how
+/// many bins a location has.
+pub fn parse_item_bin_number(s: &str) -> AHResult<i64> {
+    if s.trim() == OVERFLOW_BIN.to_string() {
+        Ok(OVERFLOW_BIN)
+    } else {
+        parse_bin_number(s)
+    }
+}
+
 pub fn bin_number_value_parser(s: &str) -> Result<i64, String> {
     parse_bin_number(s).map_err(|e| e.to_string())
 }
 
+pub fn tag_value_parser(s: &str) -> Result<String, String> {
+    if s.is_empty() || s.contains(|c: char| c == ',' || c.is_whitespace()) {
+        Err("must be a single word without commas".to_string())
+    } else {
+        Ok(s.to_string())
+    }
+}
+
+pub fn alias_value_parser(s: &str) -> Result<String, String> {
+    if s.is_empty() || s.contains(|c: char| c == '/' || c.is_whitespace()) {
+        Err("must be a single word without slashes".to_string())
+    } else {
+        Ok(s.to_string())
+    }
+}
+
+/// Parses a range of bins like `2-5`, which includes both ends.
+pub fn parse_bin_range(s: &str) -> AHResult<(i64, i64)> {
+    let (start, end) = s
+        .split_once('-')
+        .ok_or_else(|| anyhow!("bin range must be in format START-END"))?;
+    let (start, end) = (parse_bin_number(start)?, parse_bin_number(end)?);
+
+    if start > end {
+        bail!("bin range {} starts after it ends", s);
+    }
+
+    Ok((start, end))
+}
+
 #[derive(Clone)]
 pub struct ItemLocation {
     pub location: String,
     pub bin: Option<i64>,
+    /// The bins to choose from, when no particular bin is given.
+    pub bin_range: Option<(i64, i64)>,
+}
+
+impl ItemLocation {
+    /// The bins to choose from, failing if they aren't all in the location.
+    pub fn bins_in(&self, location: &Location) -> AHResult<RangeInclusive<i64>> {
+        match self.bin_range {
+            Some((start, end)) if end > location.num_bins => bail!(
+                "bin range {}-{} is outside location {}, which only has {} bins",
+                start,
+                end,
+                location.name,
+                location.num_bins
+            ),
+            Some((start, end)) => Ok(start..=end),
+            None => Ok(1..=location.num_bins),
+        }
+    }
 }
 
 impl FromStr for ItemLocation {
@@ -108,23 +402,33 @@ impl FromStr for ItemLocation {
             1 => Ok(Self {
                 location: parts[0].to_string(),
                 bin: None,
+                bin_range: None,
+            }),
+            2 if parts[1].contains('-') && !parts[1].starts_with('-') => Ok(Self {
+                location: parts[0].to_string(),
+                bin: None,
+                bin_range: Some(parse_bin_range(parts[1])?),
             }),
             2 => {
-                let bin_number = parse_bin_number(parts[1])?;
+                let bin_number = parse_item_bin_number(parts[1])?;
 
                 Ok(Self {
                     location: parts[0].to_string(),
                     bin: Some(bin_number),
+                    bin_range: None,
                 })
             }
             _ => {
-                bail!("item location must be in format LOCATION or LOCATION/BIN");
+                bail!(
+                    "item location must be in format LOCATION, LOCATION/BIN or LOCATION/START-END"
+                );
             }
         }
     }
 }
 
-#[derive(Copy, Clone, ValueEnum, Debug, PartialEq)]
+/// Item sizes, ordered from smallest to largest.
+#[derive(Copy, Clone, ValueEnum, Debug, PartialEq, Eq, PartialOrd, Ord)]
 #[clap(rename_all = "screaming_snake")]
 pub enum ItemSize {
     S,
@@ -133,6 +437,16 @@ pub enum ItemSize {
     X,
 }
 
+impl ItemSize {
+    /// The size `offset` steps larger (or smaller, if negative), wrapping around at either end.
+    pub fn cycled(self, offset: isize) -> Self {
+        let sizes = Self::value_variants();
+        let i = sizes.iter().position(|s| *s == self).unwrap() as isize;
+
+        sizes[(i + offset).rem_euclid(sizes.len() as isize) as usize]
+    }
+}
+
 impl std::str::FromStr for ItemSize {
     type Err = anyhow::Error;
     fn from_str(s: &str) -> AHResult<Self> {
@@ -146,15 +460,14 @@ impl std::str::FromStr for ItemSize {
     }
 }
 
-impl ToString for ItemSize {
-    fn to_string(&self) -> std::string::String {
-        match self {
+impl std::fmt::Display for ItemSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        f.write_str(match self {
             ItemSize::S => "S",
             ItemSize::M => "M",
             ItemSize::L => "L",
             ItemSize::X => "X",
-        }
-        .to_string()
+        })
     }
 }
 
@@ -169,6 +482,76 @@ impl From<ItemSize> for i64 {
     }
 }
 
+/// A set of item sizes, like `S,X`, or `M+` for M and larger and `L-` for L and smaller.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SizeFilter {
+    sizes: Vec<ItemSize>,
+}
+
+impl SizeFilter {
+    pub fn matches(&self, size: ItemSize) -> bool {
+        self.sizes.contains(&size)
+    }
+}
+
+impl FromStr for SizeFilter {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> AHResult<Self> {
+        let mut sizes = Vec::new();
+
+        for part in s.split(',').map(str::trim) {
+            let (size, keep): (_, fn(&ItemSize, &ItemSize) -> bool) =
+                if let Some(size) = part.strip_suffix('+') {
+                    (size, |s, size| s >= size)
+                } else if let Some(size) = part.strip_suffix('-') {
+                    (size, |s, size| s <= size)
+                } else {
+                    (part, |s, size| s == size)
+                };
+            let size = size
+                .parse::<ItemSize>()
+                .with_context(|| format!("invalid size filter \"{}\"", part))?;
+
+            sizes.extend(ItemSize::value_variants().iter().filter(|s| keep(s, &size)));
+        }
+        sizes.sort();
+        sizes.dedup();
+
+        Ok(Self { sizes })
+    }
+}
+
+/// How much room each size of item takes up in a bin, when weighing how full bins are.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SizeWeights {
+    weights: [i64; 4],
+}
+
+impl Default for SizeWeights {
+    fn default() -> Self {
+        Self {
+            weights: [ItemSize::S, ItemSize::M, ItemSize::L, ItemSize::X].map(i64::from),
+        }
+    }
+}
+
+impl SizeWeights {
+    fn index(size: ItemSize) -> usize {
+        ItemSize::value_variants()
+            .iter()
+            .position(|s| *s == size)
+            .unwrap()
+    }
+
+    pub fn get(&self, size: ItemSize) -> i64 {
+        self.weights[Self::index(size)]
+    }
+
+    pub fn set(&mut self, size: ItemSize, weight: i64) {
+        self.weights[Self::index(size)] = weight;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -178,4 +561,90 @@ mod tests {
         assert_eq!("s".parse::<ItemSize>().unwrap(), ItemSize::S);
         assert_eq!("m".parse::<ItemSize>().unwrap(), ItemSize::M);
     }
+
+    #[test]
+    fn item_sizes_should_be_ordered_from_smallest_to_largest() {
+        assert!(ItemSize::X > ItemSize::S);
+
+        let mut sizes = vec![ItemSize::L, ItemSize::X, ItemSize::S, ItemSize::M];
+        sizes.sort();
+        assert_eq!(
+            sizes,
+            vec![ItemSize::S, ItemSize::M, ItemSize::L, ItemSize::X]
+        );
+    }
+
+    #[test]
+    fn size_filters_should_parse_lists_and_ranges() {
+        let matching = |filter: &str| {
+            let filter = filter.parse::<SizeFilter>().unwrap();
+            ItemSize::value_variants()
+                .iter()
+                .copied()
+                .filter(|size| filter.matches(*size))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(matching("M+"), vec![ItemSize::M, ItemSize::L, ItemSize::X]);
+        assert_eq!(matching("l-"), vec![ItemSize::S, ItemSize::M, ItemSize::L]);
+        assert_eq!(matching("S,X"), vec![ItemSize::S, ItemSize::X]);
+        assert_eq!(matching("X, S-"), vec![ItemSize::S, ItemSize::X]);
+        assert!("Q+".parse::<SizeFilter>().is_err());
+        assert!("".parse::<SizeFilter>().is_err());
+    }
+
+    #[test]
+    fn item_templates_should_reject_unknown_placeholders() {
+        assert!("{location}/{bin} {name}".parse::<ItemTemplate>().is_ok());
+        assert!("{{name}}".parse::<ItemTemplate>().is_ok());
+        assert!("{colour}".parse::<ItemTemplate>().is_err());
+        assert!("{name".parse::<ItemTemplate>().is_err());
+        assert!("name}".parse::<ItemTemplate>().is_err());
+    }
+
+    #[test]
+    fn item_size_cycling_should_wrap_around() {
+        assert_eq!(ItemSize::S.cycled(1), ItemSize::M);
+        assert_eq!(ItemSize::X.cycled(1), ItemSize::S);
+        assert_eq!(ItemSize::S.cycled(-1), ItemSize::X);
+    }
+
+    #[test]
+    fn align_formatted_items_should_line_up_names() {
+        let formatted_item = |location_name: &str, bin_no, name: &str| FormattedItem {
+            location_name: location_name.to_string(),
+            bin_no,
+            name: name.to_string(),
+            size: "S".to_string(),
+            count: 1,
+        };
+
+        assert_eq!(
+            align_formatted_items(&[
+                formatted_item("Huge", Some(16), "Anvil"),
+                formatted_item("Tiny", None, "Batteries"),
+            ]),
+            vec!["Huge/16: Anvil (S)", "Tiny:    Batteries (S)"],
+        );
+    }
+
+    #[test]
+    fn item_locations_should_allow_the_overflow_bin() {
+        let item_location = "Kitchen/0".parse::<ItemLocation>().unwrap();
+        assert_eq!(item_location.bin, Some(OVERFLOW_BIN));
+
+        assert!(parse_bin_number("0").is_err());
+        assert!("Kitchen/-1".parse::<ItemLocation>().is_err());
+    }
+
+    #[test]
+    fn item_locations_should_allow_bin_ranges() {
+        let item_location = "Kitchen/2-5".parse::<ItemLocation>().unwrap();
+        assert_eq!(item_location.bin, None);
+        assert_eq!(item_location.bin_range, Some((2, 5)));
+
+        assert!("Kitchen/5-2".parse::<ItemLocation>().is_err());
+        assert!("Kitchen/0-2".parse::<ItemLocation>().is_err());
+        assert!("Kitchen/2-".parse::<ItemLocation>().is_err());
+    }
 }
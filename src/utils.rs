@@ -1,47 +1,298 @@
 use anyhow::bail;
-use qualia::{Object, Store, Q};
-use std::collections::HashMap;
+use clap::ValueEnum;
+use qualia::{object, Checkpoint, Object, ObjectShape, Store, Q};
+use rand::seq::SliceRandom;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 
-use crate::types::{Item, ItemSize, Location};
+use crate::types::{Item, ItemSize, Location, SizeWeights, OVERFLOW_BIN};
 use crate::AHResult;
 
-fn _choose_bin(store: &Store, location_id: i64, num_bins: i64) -> AHResult<i64> {
+/// Failures that scripts may want to tell apart, each of which exits with its own code.
+#[derive(Debug)]
+pub enum Failure {
+    /// Nothing matched what was asked for.
+    NotFound(String),
+    /// More than one thing matched where only one would do.
+    Ambiguous(String),
+    /// The store couldn't be opened or used.
+    Store(String),
+}
+
+impl Failure {
+    /// The code to exit with; other errors exit with 1, and usage errors with 2.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Failure::NotFound(_) => 3,
+            Failure::Ambiguous(_) => 4,
+            Failure::Store(_) => 5,
+        }
+    }
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Failure::NotFound(message) | Failure::Ambiguous(message) | Failure::Store(message) => {
+                f.write_str(message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Failure {}
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(0);
+
+/// Sets how much `log_verbose` prints, from the number of times `--verbose` was given.
+pub fn set_verbosity(level: u8) {
+    VERBOSITY.store(level, Ordering::SeqCst);
+}
+
+/// Prints the message to stderr if `--verbose` was given at least `level` times.
+pub fn log_verbose(level: u8, message: impl std::fmt::Display) {
+    if VERBOSITY.load(Ordering::SeqCst) >= level {
+        eprintln!("{}", message);
+    }
+}
+
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// Makes `begin_checkpoint` and undoing fail, for `--read-only`.
+pub fn set_read_only(read_only: bool) {
+    READ_ONLY.store(read_only, Ordering::SeqCst);
+}
+
+pub fn is_read_only() -> bool {
+    READ_ONLY.load(Ordering::SeqCst)
+}
+
+/// Fails if the store was opened with `--read-only`.
+pub fn check_writable() -> AHResult<()> {
+    if is_read_only() {
+        bail!("store opened read-only");
+    }
+
+    Ok(())
+}
+
+/// Starts a checkpoint, unless the store was opened with `--read-only`.
+pub fn begin_checkpoint(store: &mut Store) -> AHResult<Checkpoint<'_>> {
+    check_writable()?;
+
+    Ok(store.checkpoint()?)
+}
+
+/// Commits the checkpoint, logging its description.
+pub fn commit(checkpoint: Checkpoint, description: impl AsRef<str>) -> AHResult<()> {
+    log_verbose(1, format!("commit: {}", description.as_ref()));
+    checkpoint.commit(description)?;
+
+    Ok(())
+}
+
+fn _size_weight_key(size: ItemSize) -> String {
+    format!("size_weight_{}", size)
+}
+
+/// The size weights set with the `config` command, or the defaults for any that weren't set.
+pub fn size_weights(store: &Store) -> AHResult<SizeWeights> {
+    let mut weights = SizeWeights::default();
+
+    if let Some(settings) = store.query(Q.equal("type", "settings")).iter()?.next() {
+        for size in ItemSize::value_variants() {
+            if let Some(weight) = settings
+                .get(&_size_weight_key(*size))
+                .and_then(|w| w.as_number())
+            {
+                weights.set(*size, weight);
+            }
+        }
+    }
+
+    Ok(weights)
+}
+
+/// Changes how much room items of the given size take up.
+pub fn set_size_weight(store: &mut Store, size: ItemSize, weight: i64) -> AHResult<()> {
+    let checkpoint = begin_checkpoint(store)?;
+
+    let settings = checkpoint.query(Q.equal("type", "settings"));
+    if settings.exists()? {
+        settings.set(object!(_size_weight_key(size) => weight))?;
+    } else {
+        checkpoint.add(object!(
+            "type" => "settings",
+            _size_weight_key(size) => weight,
+        ))?;
+    }
+
+    commit(checkpoint, format!("set size.{} to {}", size, weight))?;
+
+    Ok(())
+}
+
+/// How much room the item takes up in its bin, from its volume if it has one or otherwise its size.
+pub fn item_fullness(weights: &SizeWeights, item: &Item) -> AHResult<i64> {
+    let volume = match item.volume() {
+        Some(volume) => volume,
+        None => weights.get(item.size.parse::<ItemSize>()?),
+    };
+
+    Ok(volume * item.count())
+}
+
+/// How full each bin of the location is, keyed by bin number.
+pub fn bin_fullnesses(
+    store: &Store,
+    location_id: i64,
+    num_bins: i64,
+) -> AHResult<HashMap<i64, i64>> {
     let all_location_items = store.query(Q.equal("type", "item").equal("location_id", location_id));
+    let weights = size_weights(store)?;
 
     let mut bin_fullnesses: HashMap<i64, i64> = (1..=num_bins).map(|bin_no| (bin_no, 0)).collect();
-    all_location_items
-        .iter_converted::<Item>(&store)?
-        .try_for_each(|item| -> AHResult<()> {
-            let size: ItemSize = item.size.parse::<ItemSize>()?;
+    for object in all_location_items.iter()? {
+        // Items without a bin, or in bins the location doesn't have, are left for `doctor` to find.
+        if !object.contains_key("bin_no") {
+            continue;
+        }
+
+        let item = Item::try_convert(object, store)?;
+        if let Some(fullness) = bin_fullnesses.get_mut(&item.bin_no) {
+            *fullness += item_fullness(&weights, &item)?;
+        }
+    }
 
-            *bin_fullnesses.get_mut(&item.bin_no).unwrap() += i64::from(size);
+    Ok(bin_fullnesses)
+}
+
+/// The lowest-numbered of the least full bins.
+pub fn emptiest_bin(bin_fullnesses: &HashMap<i64, i64>, num_bins: i64) -> i64 {
+    let min_fullness = bin_fullnesses.values().min().unwrap_or(&0);
+
+    (1..=num_bins)
+        .find(|bin_no| bin_fullnesses[bin_no] <= *min_fullness)
+        .unwrap()
+}
+
+/// How to choose between bins that are equally empty.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum FillOrder {
+    /// The lowest-numbered bin.
+    #[default]
+    LowFirst,
+    /// The next bin after the one last chosen this way, to spread items across the location.
+    RoundRobin,
+    /// Any of them, at random.
+    Random,
+}
+
+/// Picks one of the least full bins of the location, breaking ties according to `fill_order`.
+pub fn choose_bin(store: &Store, location: &Location, fill_order: FillOrder) -> AHResult<i64> {
+    choose_bin_in(store, location, 1..=location.num_bins, fill_order)
+}
 
-            Ok(())
-        })?;
+/// Like `choose_bin`, but only considering the given bins.
+pub fn choose_bin_in(
+    store: &Store,
+    location: &Location,
+    bins: RangeInclusive<i64>,
+    fill_order: FillOrder,
+) -> AHResult<i64> {
+    // A location that hasn't been added yet has nothing in it.
+    let fullnesses = match location.object_id {
+        Some(location_id) => {
+            log_verbose(1, format!("query: items in location {}", location_id));
+            bin_fullnesses(store, location_id, location.num_bins)?
+        }
+        None => (1..=location.num_bins).map(|bin_no| (bin_no, 0)).collect(),
+    };
+
+    Ok(choose_bin_among(&fullnesses, location, bins, fill_order))
+}
+
+/// Like `choose_bin_in`, but with already known bin fullnesses.
+pub fn choose_bin_among(
+    fullnesses: &HashMap<i64, i64>,
+    location: &Location,
+    bins: RangeInclusive<i64>,
+    fill_order: FillOrder,
+) -> i64 {
+    log_verbose(
+        2,
+        format!(
+            "bin fullnesses: {:?}",
+            fullnesses.iter().collect::<BTreeMap<_, _>>()
+        ),
+    );
 
-    let min_fullness = bin_fullnesses
-        .iter()
-        .map(|(_, fullness)| fullness)
+    let min_fullness = bins
+        .clone()
+        .map(|bin_no| fullnesses[&bin_no])
         .min()
-        .unwrap_or(&0);
-
-    Ok((1..=num_bins)
-        .find_map(|bin_no| {
-            if bin_fullnesses[&bin_no] <= *min_fullness {
-                Some(bin_no)
-            } else {
-                None
-            }
-        })
-        .unwrap())
+        .unwrap_or(0);
+    let emptiest_bins: Vec<_> = bins
+        .filter(|bin_no| fullnesses[bin_no] <= min_fullness)
+        .collect();
+
+    let bin_no = match fill_order {
+        FillOrder::LowFirst => emptiest_bins[0],
+        FillOrder::RoundRobin => {
+            let last_bin = location.last_bin().unwrap_or(0);
+
+            emptiest_bins
+                .iter()
+                .find(|bin_no| **bin_no > last_bin)
+                .copied()
+                .unwrap_or(emptiest_bins[0])
+        }
+        FillOrder::Random => *emptiest_bins.choose(&mut rand::thread_rng()).unwrap(),
+    };
+    log_verbose(1, format!("chose bin {} of {}", bin_no, location.num_bins));
+
+    bin_no
 }
 
-pub fn add_item(
-    store: &mut Store,
+/// Fails if the location already has an item with the given name, ignoring case.
+pub fn check_unique_name(store: &Store, name: &str, location: &Location) -> AHResult<()> {
+    let existing_item = store
+        .query(
+            Q.equal("type", "item")
+                .equal("location_id", location.object_id.unwrap()),
+        )
+        .iter_converted::<Item>(store)?
+        .find(|item| item.name.eq_ignore_ascii_case(name));
+
+    if let Some(existing_item) = existing_item {
+        bail!(
+            "{} already has {}; use --force to add another, or move to relocate it",
+            location.name,
+            existing_item.format()
+        );
+    }
+
+    Ok(())
+}
+
+/// What to do when adding an item would overfill a bin with a set capacity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CapacityCheck {
+    Ignore,
+    Warn,
+    Strict,
+}
+
+/// Builds the item that `add_item` would add, including choosing its bin, without committing it.
+pub fn prepare_item(
+    store: &Store,
     name: String,
     location: &Location,
     bin_no: Option<i64>,
     size: ItemSize,
+    count: i64,
+    capacity_check: CapacityCheck,
 ) -> AHResult<Item> {
     let bin_number = match bin_no {
         Some(n) => {
@@ -54,10 +305,9 @@ pub fn add_item(
             }
             n
         }
-        None => _choose_bin(&store, location.object_id.unwrap(), location.num_bins)?,
+        None => choose_bin(store, location, FillOrder::default())?,
     };
 
-    let checkpoint = store.checkpoint()?;
     let mut item = Item {
         object_id: None,
         name,
@@ -66,8 +316,234 @@ pub fn add_item(
         size: size.to_string(),
         rest: Object::new(),
     };
-    checkpoint.add_with_id(&mut item)?;
-    checkpoint.commit(format!("add item {}", item.name))?;
+    item.set_count(count);
+
+    check_capacity(store, &item, capacity_check)?;
 
     Ok(item)
 }
+
+/// Checks whether adding the item would put its bin over the location's capacity.
+pub fn check_capacity(store: &Store, item: &Item, capacity_check: CapacityCheck) -> AHResult<()> {
+    let location = &item.location;
+
+    if location.capacity().is_none() || capacity_check == CapacityCheck::Ignore {
+        return Ok(());
+    }
+
+    check_capacity_among(
+        &bin_fullnesses(store, location.object_id.unwrap(), location.num_bins)?,
+        &size_weights(store)?,
+        item,
+        capacity_check,
+    )
+}
+
+/// Like `check_capacity`, but with already known bin fullnesses.
+pub fn check_capacity_among(
+    fullnesses: &HashMap<i64, i64>,
+    weights: &SizeWeights,
+    item: &Item,
+    capacity_check: CapacityCheck,
+) -> AHResult<()> {
+    let location = &item.location;
+
+    // The overflow bin has no set capacity, being for whatever doesn't fit elsewhere.
+    if let Some(capacity) = location
+        .capacity()
+        .filter(|_| capacity_check != CapacityCheck::Ignore && item.bin_no != OVERFLOW_BIN)
+    {
+        let new_fullness = fullnesses[&item.bin_no] + item_fullness(weights, item)?;
+
+        if new_fullness > capacity {
+            let message = format!(
+                "{} would be over capacity ({}/{})",
+                item.format().format_location(),
+                new_fullness,
+                capacity
+            );
+
+            if capacity_check == CapacityCheck::Strict {
+                bail!(message);
+            }
+
+            eprintln!("Warning: {}", message);
+        }
+    }
+
+    Ok(())
+}
+
+/// Adds the item to the store, along with its location if that hasn't been added yet. For the
+/// round-robin fill order, its bin is also remembered so the next item goes in the bin after it.
+pub fn insert_item(store: &mut Store, item: &mut Item, fill_order: FillOrder) -> AHResult<()> {
+    insert_items(store, std::slice::from_mut(item), fill_order)
+}
+
+/// Like `insert_item`, but adds all of the items, which must share a location, in one checkpoint.
+pub fn insert_items(store: &mut Store, items: &mut [Item], fill_order: FillOrder) -> AHResult<()> {
+    let names = match items {
+        [] => return Ok(()),
+        [item] => format!("item {}", item.name),
+        _ => format!(
+            "items {}",
+            items
+                .iter()
+                .map(|item| item.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+
+    let checkpoint = begin_checkpoint(store)?;
+    let mut location = items[0].location.clone();
+    let description = if location.object_id.is_none() {
+        checkpoint.add_with_id(&mut location)?;
+
+        format!("add {} to new location {}", names, location.name)
+    } else {
+        format!("add {}", names)
+    };
+
+    for item in items.iter_mut() {
+        item.location.object_id = location.object_id;
+        item.set_created_now();
+        checkpoint.add_with_id(item)?;
+    }
+    if fill_order == FillOrder::RoundRobin {
+        checkpoint
+            .query(Q.id(location.object_id.unwrap()))
+            .set(object!("last_bin" => items.last().unwrap().bin_no))?;
+    }
+    commit(checkpoint, description)?;
+
+    Ok(())
+}
+
+pub fn add_item(
+    store: &mut Store,
+    name: String,
+    location: &Location,
+    bin_no: Option<i64>,
+    size: ItemSize,
+    count: i64,
+    capacity_check: CapacityCheck,
+) -> AHResult<Item> {
+    let mut item = prepare_item(store, name, location, bin_no, size, count, capacity_check)?;
+    insert_item(store, &mut item, FillOrder::default())?;
+
+    Ok(item)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::{Builder, TempDir};
+
+    fn open_test_store() -> (TempDir, Store, Location) {
+        let temp_dir = Builder::new().prefix("pachinko-utils").tempdir().unwrap();
+        let mut store = Store::open(temp_dir.path().join("pachinko-test-store.qualia")).unwrap();
+
+        let checkpoint = store.checkpoint().unwrap();
+        let location_id = checkpoint
+            .add(object!("type" => "location", "name" => "Test", "num_bins" => 3))
+            .unwrap();
+        checkpoint.commit("add test location").unwrap();
+
+        let location = store.query(Q.id(location_id)).one_as::<Location>().unwrap();
+
+        (temp_dir, store, location)
+    }
+
+    #[test]
+    fn bin_fullnesses_of_an_empty_location_should_be_zero() {
+        let (_temp_dir, store, location) = open_test_store();
+
+        assert_eq!(
+            bin_fullnesses(&store, location.object_id.unwrap(), 3).unwrap(),
+            HashMap::from([(1, 0), (2, 0), (3, 0)])
+        );
+        assert_eq!(
+            choose_bin(&store, &location, FillOrder::LowFirst).unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn bin_fullnesses_should_count_items_in_their_bins() {
+        let (_temp_dir, mut store, location) = open_test_store();
+        add_item(
+            &mut store,
+            "Anvil".to_string(),
+            &location,
+            Some(1),
+            ItemSize::X,
+            2,
+            CapacityCheck::Ignore,
+        )
+        .unwrap();
+
+        assert_eq!(
+            bin_fullnesses(&store, location.object_id.unwrap(), 3).unwrap(),
+            HashMap::from([(1, 12), (2, 0), (3, 0)])
+        );
+        assert_eq!(
+            choose_bin(&store, &location, FillOrder::LowFirst).unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn choose_bin_should_break_ties_with_the_lowest_bin() {
+        let (_temp_dir, mut store, location) = open_test_store();
+        for bin_no in [1, 3] {
+            add_item(
+                &mut store,
+                "Widget".to_string(),
+                &location,
+                Some(bin_no),
+                ItemSize::M,
+                1,
+                CapacityCheck::Ignore,
+            )
+            .unwrap();
+        }
+        add_item(
+            &mut store,
+            "Gadget".to_string(),
+            &location,
+            Some(2),
+            ItemSize::L,
+            1,
+            CapacityCheck::Ignore,
+        )
+        .unwrap();
+
+        assert_eq!(
+            bin_fullnesses(&store, location.object_id.unwrap(), 3).unwrap(),
+            HashMap::from([(1, 3), (2, 4), (3, 3)])
+        );
+        assert_eq!(
+            choose_bin(&store, &location, FillOrder::LowFirst).unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn round_robin_should_wrap_around_to_the_first_empty_bin() {
+        let (_temp_dir, store, mut location) = open_test_store();
+
+        location.rest.insert("last_bin".to_string(), 2.into());
+        assert_eq!(
+            choose_bin(&store, &location, FillOrder::RoundRobin).unwrap(),
+            3
+        );
+
+        location.rest.insert("last_bin".to_string(), 3.into());
+        assert_eq!(
+            choose_bin(&store, &location, FillOrder::RoundRobin).unwrap(),
+            1
+        );
+    }
+}
@@ -1,6 +1,7 @@
 #[macro_use]
 mod common;
 use common::*;
+use predicates::prelude::*;
 
 #[test]
 fn adding_an_item_to_a_specified_bin() {
@@ -78,7 +79,7 @@ fn items_should_sort_by_location_then_bin_then_alphabetically() {
     ctx.populate();
 
     ctx.assert_pch(&["add", "test/4", "Test item", "M"]);
-    ctx.assert_pch(&["add", "test/3", "Test item", "M"]);
+    ctx.assert_pch(&["add", "test/3", "Test item", "M", "--force"]);
     ctx.assert_pch(&["add", "huge/6", "Test item", "M"]);
     ctx.assert_pch(&["add", "test/4", "Test blight'em", "M"]);
 
@@ -89,3 +90,245 @@ Test/4: Test blight'em.*
 Test/4: Test item",
     );
 }
+
+#[test]
+fn adding_items_should_show_counts_greater_than_one() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/4", "AA batteries", "M", "-n", "8"])
+        .only_stdout_contains("Test/4: AA batteries (M x8)");
+    ctx.assert_pch(&["add", "Test/4", "Test item", "M", "--count", "1"])
+        .only_stdout_contains("Test/4: Test item (M)\n");
+}
+
+#[test]
+fn adding_items_with_a_nonpositive_count_should_fail() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch_fails(&["add", "Test/4", "Test item", "-n", "0"]);
+}
+
+#[test]
+fn adding_an_item_should_resolve_location_aliases() {
+    init!(ctx);
+    ctx.populate();
+    ctx.assert_pch(&["add-location", "Kitchen", "4", "--alias", "k"]);
+
+    ctx.assert_pch(&["add", "K/2", "Coffee filters"])
+        .only_stdout_contains("Kitchen/2: Coffee filters");
+}
+
+#[test]
+fn adding_an_item_over_capacity_should_warn() {
+    init!(ctx);
+    ctx.assert_pch(&["add-location", "Shelf", "1", "--capacity", "4"]);
+
+    ctx.assert_pch(&["add", "Shelf", "Blender", "M"])
+        .only_stdout_contains("Shelf: Blender (M)");
+    ctx.assert_pch(&["add", "Shelf", "Toaster", "S"])
+        .stderr(predicate::str::contains(
+            "Warning: Shelf would be over capacity (5/4)",
+        ))
+        .stdout(predicate::str::contains("Shelf: Toaster (S)"));
+}
+
+#[test]
+fn adding_an_item_over_capacity_should_fail_when_strict() {
+    init!(ctx);
+    ctx.assert_pch(&["add-location", "Shelf", "2", "--capacity", "4"]);
+
+    ctx.assert_pch(&["add", "Shelf/2", "Blender", "L"]);
+    ctx.assert_pch_fails(&["add", "Shelf/2", "Toaster", "--strict"])
+        .only_stderr_matches("Shelf/2 would be over capacity \\(6/4\\)");
+    ctx.assert_pch(&["items"])
+        .only_stdout_contains("Shelf/2: Blender (L)\n");
+}
+
+#[test]
+fn adding_an_item_with_dry_run_should_not_add_it() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test", "Test item", "--dry-run"])
+        .only_stdout_contains("(dry run) Test/1: Test item (S)");
+    ctx.assert_pch(&["items"]).is_silent();
+}
+
+#[test]
+fn adding_a_duplicate_item_should_fail_unless_forced() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/1", "Hammer"]);
+    ctx.assert_pch_fails(&["add", "Test/2", "hammer"])
+        .only_stderr_matches(r"Test already has Test/1: Hammer \(S\); use --force");
+    ctx.assert_pch(&["add", "Tiny", "Hammer"])
+        .only_stdout_contains("Tiny: Hammer");
+    ctx.assert_pch(&["add", "Test/2", "hammer", "--allow-duplicate"])
+        .only_stdout_contains("Test/2: hammer");
+}
+
+#[test]
+fn adding_an_item_to_the_overflow_bin() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Tiny/0", "Box of cables"])
+        .only_stdout_contains("Tiny (overflow): Box of cables");
+    ctx.assert_pch(&["add", "Test/0", "Spare hinges", "X"]);
+    ctx.assert_pch(&["add", "Test", "Screws"])
+        .only_stdout_contains("Test/1: Screws");
+    ctx.assert_pch(&["doctor"])
+        .only_stdout_contains("No problems found");
+}
+
+#[test]
+fn adding_verbosely_logs_the_chosen_bin() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/1", "Hammer"]);
+    ctx.assert_pch(&["add", "-v", "Test", "Saw"])
+        .stdout("Test/2: Saw (S)\n")
+        .stderr(predicate::str::contains(
+            "chose bin 2 of 4\ncommit: add item Saw\n",
+        ))
+        .stderr(predicate::str::contains("bin fullnesses").not());
+    ctx.assert_pch(&["add", "-vv", "Test", "Drill"])
+        .stderr(predicate::str::contains(
+            "bin fullnesses: {1: 2, 2: 2, 3: 0, 4: 0}\nchose bin 3 of 4\n",
+        ));
+}
+
+#[test]
+fn adding_an_item_with_a_custom_volume() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/1", "Crate", "--volume", "12", "-n", "2"])
+        .only_stdout_contains("Test/1: Crate (12u x2)\n");
+    ctx.assert_pch(&["items"])
+        .only_stdout_contains("Test/1: Crate (12u x2)\n");
+
+    ctx.assert_pch(&["add-location", "Shelf", "1", "--capacity", "10"]);
+    ctx.assert_pch_fails(&["add", "Shelf", "Barrel", "--volume", "12", "--strict"])
+        .only_stderr_matches(r"Shelf would be over capacity \(12/10\)");
+}
+
+#[test]
+fn adding_an_item_can_create_its_location() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch_fails(&["add", "Garage", "Rake"]);
+    ctx.assert_pch(&["add", "Garage", "Rake", "--create-location"])
+        .only_stdout_contains("Garage: Rake (S)\n");
+    ctx.assert_pch(&["add", "Shed/3", "Hoe", "--create-location=4"])
+        .only_stdout_contains("Shed/3: Hoe (S)\n");
+    ctx.assert_pch(&["add", "Shed", "Shovel", "--create-location"])
+        .only_stdout_contains("Shed/1: Shovel (S)\n");
+    ctx.assert_pch_fails(&["add", "Attic/3", "Box", "--create-location"])
+        .only_stderr_matches("location Attic only has 1 bins");
+
+    ctx.assert_pch(&["undo"])
+        .only_stdout_contains("Undid: add item Shovel\n");
+    ctx.assert_pch(&["undo"])
+        .only_stdout_contains("Undid: add item Hoe to new location Shed\n");
+    ctx.assert_pch(&["locations"])
+        .only_stdout_contains("Test (4 bins)\nTiny\nHuge (16 bins)\nGarage\n");
+}
+
+#[test]
+fn adding_an_item_should_not_create_a_location_for_an_ambiguous_name() {
+    init!(ctx);
+
+    ctx.assert_pch(&["add-location", "Shelf A", "1"]);
+    ctx.assert_pch(&["add-location", "Shelf B", "1"]);
+
+    ctx.assert_pch_fails(&["add", "shelf", "Hammer", "--create-location"])
+        .code(4)
+        .only_stderr_matches("it matched Shelf A, Shelf B");
+    ctx.assert_pch(&["locations"])
+        .only_stdout_matches("^Shelf A\nShelf B\n$");
+}
+
+#[test]
+fn quiet_additions_should_print_nothing() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "-q", "Test/4", "Test item"])
+        .is_silent();
+    ctx.assert_pch(&["items"])
+        .only_stdout_contains("Test/4: Test item");
+}
+
+#[test]
+fn exact_locations_should_match_case() {
+    init!(ctx);
+
+    ctx.assert_pch(&["add-location", "Box", "1"]);
+    ctx.assert_pch(&["add-location", "Big box", "1"]);
+
+    ctx.assert_pch_fails(&["add", "--exact", "box", "Hat"])
+        .only_stderr_matches("did not match exactly one location");
+    ctx.assert_pch_fails(&["add", "--exact", "big", "Hat"])
+        .only_stderr_matches("did not match exactly one location");
+
+    ctx.assert_pch(&["add-location", "box", "1"]);
+    ctx.assert_pch(&["add", "--exact", "box", "Hat"])
+        .only_stdout_contains("box: Hat");
+    ctx.assert_pch(&["add", "--exact", "Box", "Cap"])
+        .only_stdout_contains("Box: Cap");
+}
+
+#[test]
+fn several_items_should_be_added_at_once() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/1", "Hammer", "L"]);
+
+    ctx.assert_pch(&["add", "Test", "Fork", "Spoon", "Knife", "Ladle", "M"])
+        .only_stdout_matches(
+            r"^Test/2: Fork \(M\)
+Test/3: Spoon \(M\)
+Test/4: Knife \(M\)
+Test/2: Ladle \(M\)
+$",
+        );
+    ctx.assert_pch(&["undo"])
+        .only_stdout_contains("Undid: add items Fork, Spoon, Knife, Ladle");
+    ctx.assert_pch(&["items"])
+        .only_stdout_matches("^Test/1: Hammer \\(L\\)\n$");
+}
+
+#[test]
+fn several_items_should_share_a_size_option() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Tiny", "Fork", "Spoon", "--size", "X"])
+        .only_stdout_matches("^Tiny: Fork \\(X\\)\nTiny: Spoon \\(X\\)\n$");
+    ctx.assert_pch(&["add", "Tiny", "Knife", "Ladle", "m"])
+        .only_stdout_matches("^Tiny: Knife \\(M\\)\nTiny: Ladle \\(M\\)\n$");
+    ctx.assert_pch(&["add", "Tiny", "Whisk", "--size", "l"])
+        .only_stdout_matches("^Tiny: Whisk \\(L\\)\n$");
+    ctx.assert_pch_fails(&["add", "Tiny", "Knife", "M", "--size", "X"])
+        .only_stderr_matches("size given both");
+}
+
+#[test]
+fn several_items_with_the_same_name_should_need_force() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch_fails(&["add", "Tiny", "Fork", "fork"])
+        .only_stderr_matches("fork was given more than once");
+    ctx.assert_pch(&["items"]).is_silent();
+
+    ctx.assert_pch(&["add", "Tiny", "Fork", "fork", "--force"])
+        .only_stdout_matches("^Tiny: Fork \\(S\\)\nTiny: fork \\(S\\)\n$");
+}
@@ -17,9 +17,9 @@ fn items_should_distribute_evenly() {
     ctx.populate();
 
     ctx.assert_pch(&["add", "test", "Test item"]);
-    ctx.assert_pch(&["add", "test", "Test item"]);
-    ctx.assert_pch(&["add", "test", "Test item"]);
-    ctx.assert_pch(&["add", "test", "Test item"]);
+    ctx.assert_pch(&["add", "test", "Test item", "--force"]);
+    ctx.assert_pch(&["add", "test", "Test item", "--force"]);
+    ctx.assert_pch(&["add", "test", "Test item", "--force"]);
 
     ctx.assert_pch(&["items"]).only_stdout_matches(
         "Test/1: Test item .*
@@ -57,3 +57,75 @@ fn items_should_distribute_to_the_first_possible_slot() {
     ctx.assert_pch(&["add", "test", "X3", "X"])
         .only_stdout_contains("Test/3: X3");
 }
+
+#[test]
+fn items_should_distribute_by_size_times_count() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "test/1", "Batteries", "S", "-n", "4"]);
+    ctx.assert_pch(&["add", "test/2", "X2", "X"]);
+    ctx.assert_pch(&["add", "test/3", "X3", "X"]);
+    ctx.assert_pch(&["add", "test/4", "X4", "X"]);
+
+    ctx.assert_pch(&["add", "test", "Small", "S"])
+        .only_stdout_contains("Test/2: Small");
+}
+
+#[test]
+fn round_robin_fill_order_should_continue_after_the_last_chosen_bin() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "test", "Hammer", "--fill-order", "round-robin"])
+        .only_stdout_contains("Test/1: Hammer (S)");
+    ctx.assert_pch(&["delete", "Hammer"]);
+
+    ctx.assert_pch(&["add", "test", "Saw", "--fill-order", "round-robin"])
+        .only_stdout_contains("Test/2: Saw (S)");
+    ctx.assert_pch(&["add", "test", "Drill"])
+        .only_stdout_contains("Test/1: Drill (S)");
+}
+
+#[test]
+fn random_fill_order_should_still_choose_the_most_empty_slot() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "test/1", "A"]);
+    ctx.assert_pch(&["add", "test/2", "B"]);
+    ctx.assert_pch(&["add", "test/4", "D"]);
+
+    ctx.assert_pch(&["add", "test", "C", "--fill-order", "random"])
+        .only_stdout_contains("Test/3: C (S)");
+}
+
+#[test]
+fn items_with_a_custom_volume_should_count_it_toward_fullness() {
+    init!(ctx);
+    ctx.assert_pch(&["add-location", "Pair", "2"]);
+
+    ctx.assert_pch(&["add", "pair/1", "Lamp", "L"]);
+    ctx.assert_pch(&["add", "pair/2", "Crate", "--volume", "5"]);
+
+    ctx.assert_pch(&["add", "pair", "Mug"])
+        .only_stdout_contains("Pair/1: Mug (S)");
+}
+
+#[test]
+fn items_should_distribute_within_a_bin_range() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "test/2-3", "A"])
+        .only_stdout_contains("Test/2: A (S)");
+    ctx.assert_pch(&["add", "test/2-3", "B"])
+        .only_stdout_contains("Test/3: B (S)");
+    ctx.assert_pch(&["add", "test/2-3", "C"])
+        .only_stdout_contains("Test/2: C (S)");
+
+    ctx.assert_pch_fails(&["add", "test/3-9", "D"])
+        .only_stderr_matches("bin range 3-9 is outside location Test, which only has 4 bins");
+    ctx.assert_pch_fails(&["add", "test/3-2", "D"])
+        .only_stderr_matches("bin range 3-2 starts after it ends");
+}
@@ -1,4 +1,5 @@
 use predicates::prelude::*;
+use predicates::reflection;
 pub use tempfile::{Builder, TempDir};
 
 pub struct TestContext {
@@ -45,7 +46,9 @@ impl TestContext {
     }
 }
 
+#[allow(dead_code)]
 pub trait CommandAssertHelpers {
+    #[allow(clippy::wrong_self_convention)]
     fn is_silent(self) -> Self;
     fn only_stdout_contains(self, s: impl AsRef<str>) -> Self;
     fn only_stdout_matches(self, s: impl AsRef<str>) -> Self;
@@ -74,6 +77,59 @@ impl CommandAssertHelpers for assert_cmd::assert::Assert {
     }
 }
 
+pub struct JsonMatcher {
+    expected: serde_json::Value,
+}
+
+/// Parses the output, leaving out when items were added, as that changes from run to run.
+fn _parse_json_output(variable: &[u8]) -> serde_json::Value {
+    let mut actual: serde_json::Value = serde_json::from_slice(variable).unwrap();
+
+    if let Some(objects) = actual.as_array_mut() {
+        for object in objects.iter_mut().filter_map(|o| o.as_object_mut()) {
+            object.remove("created_at");
+        }
+    }
+
+    actual
+}
+
+impl predicates::Predicate<[u8]> for JsonMatcher {
+    fn eval(&self, variable: &[u8]) -> bool {
+        _parse_json_output(variable) == self.expected
+    }
+
+    fn find_case<'a>(&'a self, expected: bool, variable: &[u8]) -> Option<reflection::Case<'a>> {
+        let actual_value = _parse_json_output(variable);
+        let result = self.expected == actual_value;
+        if result == expected {
+            Some(
+                reflection::Case::new(Some(self), result)
+                    .add_product(reflection::Product::new("actual value", actual_value)),
+            )
+        } else {
+            None
+        }
+    }
+}
+
+impl reflection::PredicateReflection for JsonMatcher {}
+
+impl std::fmt::Display for JsonMatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        write!(f, "var.is_json({})", self.expected)
+    }
+}
+
+#[allow(dead_code)]
+pub fn is_json(expected_str: impl AsRef<str>) -> JsonMatcher {
+    let expected_str = expected_str.as_ref();
+
+    let expected: serde_json::Value = serde_json::from_str(expected_str).unwrap();
+
+    JsonMatcher { expected }
+}
+
 #[allow(unused_macros)]
 macro_rules! init {
     ($ctx:ident) => {
@@ -0,0 +1,39 @@
+#[macro_use]
+mod common;
+use common::*;
+
+use qualia::Store;
+
+#[test]
+fn compacting_should_forget_old_undone_actions() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/1", "Hammer"]);
+    ctx.assert_pch(&["add", "Test/2", "Saw"]);
+    ctx.assert_pch(&["add", "Test/3", "Drill"]);
+    ctx.assert_pch(&["undo"]);
+    ctx.assert_pch(&["undo"]);
+    ctx.assert_pch(&["undo"]);
+
+    ctx.assert_pch(&["compact", "--keep-redo", "1"])
+        .only_stdout_matches("^Forgot 2 undone action\\(s\\)\nReclaimed [0-9]+ bytes\n$");
+    ctx.assert_pch(&["locations"])
+        .only_stdout_contains("Test (4 bins)");
+
+    ctx.assert_pch(&["redo"])
+        .only_stdout_contains("Redid: add item Hammer");
+    ctx.assert_pch(&["redo"])
+        .only_stdout_contains("Nothing to redo");
+}
+
+#[test]
+fn compacting_should_refuse_while_the_store_is_open() {
+    init!(ctx);
+    ctx.populate();
+
+    let _store = Store::open(ctx.store_path()).unwrap();
+
+    ctx.assert_pch_fails(&["compact"])
+        .only_stderr_matches("store is in use");
+}
@@ -0,0 +1,54 @@
+#[macro_use]
+mod common;
+use common::*;
+
+#[test]
+fn config_should_show_default_size_weights() {
+    init!(ctx);
+
+    ctx.assert_pch(&["config"])
+        .only_stdout_matches(r"^size.S = 2\nsize.M = 3\nsize.L = 4\nsize.X = 6\n$");
+}
+
+#[test]
+fn size_weights_should_be_changeable() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["config", "size.m", "5"]).is_silent();
+    ctx.assert_pch(&["config", "size.M"])
+        .only_stdout_matches(r"^size.M = 5\n$");
+
+    ctx.assert_pch(&["add", "Test/1", "Medium", "M"]);
+    ctx.assert_pch(&["stats"])
+        .only_stdout_contains("Test/1: 5\n");
+
+    ctx.assert_pch(&["undo"])
+        .only_stdout_contains("Undid: add item Medium");
+    ctx.assert_pch(&["undo"])
+        .only_stdout_contains("Undid: set size.M to 5");
+    ctx.assert_pch(&["config", "size.M"])
+        .only_stdout_matches(r"^size.M = 3\n$");
+}
+
+#[test]
+fn size_weights_should_affect_bin_choice() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["config", "size.S", "10"]);
+    ctx.assert_pch(&["add", "Test/1", "Small", "S"]);
+    ctx.assert_pch(&["add", "Test/2", "Rug", "L"]);
+    ctx.assert_pch(&["add", "Test/3", "Sofa", "L"]);
+    ctx.assert_pch(&["add", "Test/4", "Desk", "L"]);
+    ctx.assert_pch(&["add", "Test", "Medium", "M"])
+        .only_stdout_contains("Test/2: Medium");
+}
+
+#[test]
+fn unknown_settings_should_fail() {
+    init!(ctx);
+
+    ctx.assert_pch_fails(&["config", "size.Q"])
+        .only_stderr_matches("must be one of size.S, size.M, size.L or size.X");
+}
@@ -19,6 +19,7 @@ fn console_has_help() -> rexpect::errors::Result<()> {
     p.exp_string("items")?;
     p.exp_string("locations")?;
     p.exp_string("quickadd")?;
+    p.exp_string("clear")?;
 
     p.process.exit()?;
 
@@ -117,3 +118,41 @@ fn console_does_not_crash_with_empty_input() -> rexpect::errors::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn console_remembers_history_between_sessions() -> rexpect::errors::Result<()> {
+    init!(ctx);
+    ctx.populate();
+
+    let mut p = spawn_command(ctx.pch_cmd(&["console"]), Some(1000))?;
+    p.exp_string("pachinko> ")?;
+    p.send_line("locations")?;
+    p.exp_string("pachinko> ")?;
+    p.send_line("quit")?;
+    p.process.wait()?;
+
+    let mut p = spawn_command(ctx.pch_cmd(&["console"]), Some(1000))?;
+    p.exp_string("pachinko> ")?;
+    // Up past "quit" to "locations", then Enter.
+    p.send("\x1b[A\x1b[A")?;
+    p.send_line("")?;
+    p.exp_regex("Test.*4")?;
+
+    p.process.exit()?;
+
+    Ok(())
+}
+
+#[test]
+fn console_runs_piped_commands() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.pch_assert_cmd(&["console"])
+        .write_stdin("add Test/2 First\n\nadd Nowhere Second\nitems\nquit\nadd Test Third\n")
+        .assert()
+        .success()
+        .only_stdout_matches(
+            r#"^Test/2: First \(S\)\nError: location name "Nowhere" did not match exactly one location\nTest/2: First \(S\)\n$"#,
+        );
+}
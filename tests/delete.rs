@@ -2,6 +2,8 @@
 mod common;
 use common::*;
 
+use rexpect::session::spawn_command;
+
 #[test]
 fn items_should_be_deletable() {
     init!(ctx);
@@ -20,6 +22,33 @@ fn items_should_be_deletable() {
     ctx.assert_pch(&["items"]).is_silent();
 }
 
+#[test]
+fn quiet_deletions_should_print_nothing() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/4", "Test item"]);
+    ctx.assert_pch(&["delete", "--quiet", "Test"]).is_silent();
+    ctx.assert_pch(&["items"]).is_silent();
+}
+
+#[test]
+fn exact_deletions_should_only_match_the_whole_name() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/1", "Hat"]);
+    ctx.assert_pch(&["add", "Test/2", "Hat box"]);
+
+    ctx.assert_pch(&["delete", "--exact", "hat"]).is_silent();
+    ctx.assert_pch(&["items", "--exact", "Hat"])
+        .only_stdout_matches(r"^Test/1: Hat \(S\)\n$");
+    ctx.assert_pch(&["delete", "--exact", "Hat"])
+        .only_stdout_contains("Deleted Test/1: Hat");
+    ctx.assert_pch(&["items"])
+        .only_stdout_matches(r"^Test/2: Hat box \(S\)\n$");
+}
+
 #[test]
 fn deleting_should_be_undoable() {
     init!(ctx);
@@ -60,7 +89,7 @@ fn deleting_multiple_items_with_confirmation_should_be_possible() {
         .only_stdout_contains("Test/1: Don't delete me");
     ctx.assert_pch(&["add", "Test/1", "Also test item"])
         .only_stdout_contains("Test/1: Also test item");
-    ctx.assert_pch(&["delete", "--all", "Test"])
+    ctx.assert_pch(&["delete", "--all", "--yes", "Test"])
         .only_stdout_contains(
             "Deleted Test/1: Also test item (S)
 Deleted Test/4: Test item (S)",
@@ -68,3 +97,71 @@ Deleted Test/4: Test item (S)",
     ctx.assert_pch(&["items"])
         .only_stdout_matches("Test/1: Don't delete me");
 }
+
+#[test]
+fn items_should_be_deletable_by_id() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/4", "Test item"]);
+    ctx.assert_pch(&["add", "Test/1", "Test item", "--force"]);
+    ctx.assert_pch(&["delete", "--id", "5"])
+        .only_stdout_contains("Deleted Test/1: Test item");
+    ctx.assert_pch(&["items"])
+        .only_stdout_matches(r"^Test/4: Test item \(S\)\n$");
+}
+
+#[test]
+fn deleting_by_id_should_reject_other_objects_and_name_patterns() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/4", "Test item"]);
+    ctx.assert_pch_fails(&["delete", "--id", "1"])
+        .only_stderr_matches("no item with ID 1");
+    ctx.assert_pch_fails(&["delete", "--id", "4", "Test"]);
+    ctx.assert_pch(&["items"])
+        .only_stdout_contains("Test/4: Test item");
+}
+
+#[test]
+fn deleting_multiple_items_without_a_terminal_should_require_yes() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/4", "Test item"]);
+    ctx.assert_pch(&["add", "Test/1", "Also test item"]);
+    ctx.assert_pch_fails(&["delete", "--all", "Test"])
+        .only_stderr_matches("found 2 matching items; use --yes");
+    ctx.assert_pch(&["items"])
+        .only_stdout_contains("Test/1: Also test item (S)\nTest/4: Test item (S)\n");
+}
+
+#[test]
+fn deleting_multiple_items_should_ask_first() -> rexpect::errors::Result<()> {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/4", "Test item"]);
+    ctx.assert_pch(&["add", "Test/1", "Also test item"]);
+
+    let mut p = spawn_command(ctx.pch_cmd(&["delete", "--all", "Test"]), Some(1000))?;
+    p.exp_string("    Test/4: Test item (S)")?;
+    p.exp_string("Delete these 2 items? [y/N] ")?;
+    p.send_line("n")?;
+    p.exp_string("Nothing deleted")?;
+    p.process.wait()?;
+
+    ctx.assert_pch(&["items"])
+        .only_stdout_contains("Test/1: Also test item (S)\nTest/4: Test item (S)\n");
+
+    let mut p = spawn_command(ctx.pch_cmd(&["delete", "--all", "Test"]), Some(1000))?;
+    p.exp_string("Delete these 2 items? [y/N] ")?;
+    p.send_line("y")?;
+    p.exp_string("Deleted Test/1: Also test item (S)")?;
+    p.process.wait()?;
+
+    ctx.assert_pch(&["items"]).is_silent();
+
+    Ok(())
+}
@@ -0,0 +1,99 @@
+#[macro_use]
+mod common;
+use common::*;
+
+use qualia::{object, Object, Store, Q};
+
+fn break_store(ctx: &TestContext) {
+    let mut store = Store::open(ctx.store_path()).unwrap();
+
+    let checkpoint = store.checkpoint().unwrap();
+    checkpoint
+        .query(Q.equal("type", "location").equal("name", "Tiny"))
+        .delete()
+        .unwrap();
+    checkpoint
+        .query(Q.equal("type", "item").equal("name", "Wrench"))
+        .set(object!("bin_no" => 9))
+        .unwrap();
+    checkpoint.commit("break store").unwrap();
+}
+
+#[test]
+fn doctor_should_find_no_problems_in_a_healthy_store() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/1", "Hammer"]);
+    ctx.assert_pch(&["doctor"])
+        .only_stdout_matches(r"^No problems found\n$");
+}
+
+#[test]
+fn doctor_should_report_items_in_missing_locations_and_bins() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Tiny", "Hammer"]);
+    ctx.assert_pch(&["add", "Test/2", "Wrench"]);
+    break_store(&ctx);
+
+    ctx.assert_pch(&["doctor"]).only_stdout_matches(
+        r"^Item \d+ \(Hammer\) is not in an existing location
+Item \d+ \(Wrench\) is in bin 9 of Test, which only has 4 bins
+$",
+    );
+}
+
+#[test]
+fn doctor_should_fix_problems() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Tiny", "Hammer"]);
+    ctx.assert_pch(&["add", "Test/1", "Wrench"]);
+    ctx.assert_pch(&["add", "Test/1", "Anvil", "X"]);
+    break_store(&ctx);
+
+    ctx.assert_pch(&["doctor", "--fix"])
+        .only_stdout_contains("Fixed 2 problems\n");
+    ctx.assert_pch(&["items"]).only_stdout_matches(
+        r"^Test/1:   Anvil \(X\)
+Test/2:   Wrench \(S\)
+Unsorted: Hammer \(S\)
+$",
+    );
+    ctx.assert_pch(&["doctor"])
+        .only_stdout_matches(r"^No problems found\n$");
+}
+
+#[test]
+fn doctor_should_report_and_fix_items_without_a_bin() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/1", "Anvil", "X"]);
+    ctx.assert_pch(&["add", "Test/2", "Wrench"]);
+
+    let mut store = Store::open(ctx.store_path()).unwrap();
+    let checkpoint = store.checkpoint().unwrap();
+    let mut wrench = checkpoint
+        .query(Q.equal("type", "item").equal("name", "Wrench"))
+        .one()
+        .unwrap();
+    checkpoint
+        .query(Q.id(wrench["object_id"].as_number().unwrap()))
+        .delete()
+        .unwrap();
+    wrench.remove("object_id");
+    wrench.remove("bin_no");
+    checkpoint.add(wrench).unwrap();
+    checkpoint.commit("remove bin").unwrap();
+
+    ctx.assert_pch(&["doctor"])
+        .only_stdout_matches(r"^Item \d+ \(Wrench\) is in Test but has no bin\n$");
+    ctx.assert_pch(&["doctor", "--fix"])
+        .only_stdout_contains("Fixed 1 problems\n");
+    ctx.assert_pch(&["items"])
+        .only_stdout_matches(r"^Test/1: Anvil \(X\)\nTest/2: Wrench \(S\)\n$");
+}
@@ -2,49 +2,6 @@
 mod common;
 use common::*;
 
-use predicates::reflection;
-
-struct JsonMatcher {
-    expected: serde_json::Value,
-}
-
-impl predicates::Predicate<[u8]> for JsonMatcher {
-    fn eval(&self, variable: &[u8]) -> bool {
-        let actual: serde_json::Value = serde_json::from_slice(variable).unwrap();
-
-        actual == self.expected
-    }
-
-    fn find_case<'a>(&'a self, expected: bool, variable: &[u8]) -> Option<reflection::Case<'a>> {
-        let actual_value: serde_json::Value = serde_json::from_slice(variable).unwrap();
-        let result = self.expected == actual_value;
-        if result == expected {
-            Some(
-                reflection::Case::new(Some(self), result)
-                    .add_product(reflection::Product::new("actual value", actual_value)),
-            )
-        } else {
-            None
-        }
-    }
-}
-
-impl reflection::PredicateReflection for JsonMatcher {}
-
-impl std::fmt::Display for JsonMatcher {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
-        write!(f, "var.is_json({})", self.expected)
-    }
-}
-
-fn is_json(expected_str: impl AsRef<str>) -> JsonMatcher {
-    let expected_str = expected_str.as_ref();
-
-    let expected: serde_json::Value = serde_json::from_str(expected_str).unwrap();
-
-    JsonMatcher { expected }
-}
-
 #[test]
 fn can_dump_items() {
     init!(ctx);
@@ -105,3 +62,99 @@ fn can_dump_items() {
         "#,
         ));
 }
+
+#[test]
+fn dumps_should_include_counts_only_when_given() {
+    init!(ctx);
+
+    ctx.assert_pch(&["add-location", "Tiny", "1"]);
+    ctx.assert_pch(&["add", "tiny", "Single item"]);
+    ctx.assert_pch(&["add", "tiny", "Batteries", "-n", "8"]);
+
+    ctx.assert_pch(&["dump"])
+        .stderr(predicates::str::is_empty())
+        .stdout(is_json(
+            r#"
+[
+    {
+        "object_id": 1,
+        "name": "Tiny",
+        "num_bins": 1,
+        "type": "location"
+    },
+    {
+        "object_id": 2,
+        "bin_no": 1,
+        "location_id": 1,
+        "name": "Single item",
+        "size": "S",
+        "type": "item"
+    },
+    {
+        "object_id": 3,
+        "bin_no": 1,
+        "count": 8,
+        "location_id": 1,
+        "name": "Batteries",
+        "size": "S",
+        "type": "item"
+    }
+]
+        "#,
+        ));
+}
+
+#[test]
+fn can_dump_one_object_per_line() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "test/4", "Test item", "M"]);
+
+    let dumped: serde_json::Value =
+        serde_json::from_slice(&ctx.assert_pch(&["dump"]).get_output().stdout).unwrap();
+
+    let dumped_lines: Vec<serde_json::Value> = String::from_utf8(
+        ctx.assert_pch(&["dump", "--jsonl"])
+            .stderr(predicates::str::is_empty())
+            .get_output()
+            .stdout
+            .clone(),
+    )
+    .unwrap()
+    .lines()
+    .map(|line| serde_json::from_str(line).unwrap())
+    .collect();
+
+    assert_eq!(dumped_lines.len(), 4);
+    assert_eq!(serde_json::Value::Array(dumped_lines), dumped);
+}
+
+#[test]
+fn restoring_a_dump_should_bring_back_items_and_locations() {
+    init!(ctx);
+    ctx.populate();
+    ctx.assert_pch(&["add-location", "Drawer", "2", "--parent", "Huge"]);
+    ctx.assert_pch(&["add", "drawer/2", "Spoon"]);
+    ctx.assert_pch(&["add", "test/4", "Test item", "M"]);
+
+    let dump_path = ctx.temp_dir.path().join("dump.json");
+    std::fs::write(&dump_path, &ctx.assert_pch(&["dump"]).get_output().stdout).unwrap();
+    let locations = ctx.assert_pch(&["locations"]).get_output().stdout.clone();
+
+    init!(new_ctx);
+    new_ctx.assert_pch(&["add-location", "Other", "1"]);
+    new_ctx
+        .assert_pch_fails(&["restore", dump_path.to_str().unwrap()])
+        .only_stderr_matches("store is not empty; use --force");
+
+    new_ctx
+        .assert_pch(&["restore", "--force", dump_path.to_str().unwrap()])
+        .only_stdout_contains("Restored 6 objects\n");
+    new_ctx
+        .assert_pch(&["items"])
+        .only_stdout_contains("Drawer/2: Spoon (S)\nTest/4:   Test item (M)\n");
+    new_ctx
+        .assert_pch(&["locations"])
+        .stdout(String::from_utf8(locations).unwrap());
+}
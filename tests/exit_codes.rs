@@ -0,0 +1,42 @@
+#[macro_use]
+mod common;
+use common::*;
+
+#[test]
+fn missing_locations_and_items_should_exit_with_3() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch_fails(&["add", "Nowhere", "Hammer"]).code(3);
+    ctx.assert_pch_fails(&["move", "Hammer", "Test"]).code(3);
+}
+
+#[test]
+fn ambiguous_matches_should_exit_with_4() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add-location", "Top shelf", "1"]);
+    ctx.assert_pch(&["add-location", "Bottom shelf", "1"]);
+    ctx.assert_pch_fails(&["add", "shelf", "Hammer"]).code(4);
+
+    ctx.assert_pch(&["add", "Test", "Red hat"]);
+    ctx.assert_pch(&["add", "Test", "Blue hat"]);
+    ctx.assert_pch_fails(&["delete", "hat"]).code(4);
+}
+
+#[test]
+fn unusable_stores_should_exit_with_5() {
+    init!(ctx);
+
+    ctx.assert_pch_fails(&["items", "--read-only"]).code(5);
+}
+
+#[test]
+fn other_errors_should_exit_with_1() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch_fails(&["swap-bins", "Test", "1", "1"])
+        .code(1);
+}
@@ -0,0 +1,25 @@
+#[macro_use]
+mod common;
+use common::*;
+
+#[test]
+fn exporting_with_no_items_should_only_print_a_header() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["export", "--format", "csv"])
+        .only_stdout_matches(r"^location,bin,name,size\n$");
+}
+
+#[test]
+fn exporting_should_write_items_as_csv() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/3", "Nuts, bolts", "M"]);
+    ctx.assert_pch(&["add", "Tiny", "Test item"]);
+
+    ctx.assert_pch(&["export"]).only_stdout_contains(
+        "location,bin,name,size\nTest,3,\"Nuts, bolts\",M\nTiny,1,Test item,S\n",
+    );
+}
@@ -0,0 +1,41 @@
+#[macro_use]
+mod common;
+use common::*;
+
+#[test]
+fn find_should_match_across_location_and_name() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "test/2", "AA batteries", "M"]);
+    ctx.assert_pch(&["add", "huge/6", "Battery charger", "M"]);
+    ctx.assert_pch(&["add", "tiny", "Screwdriver"]);
+
+    ctx.assert_pch(&["find", "bat", "test"])
+        .only_stdout_matches(r"^Test/2: AA batteries \(M\)\n$");
+    ctx.assert_pch(&["find", "bat"]).only_stdout_matches(
+        r"^(Test/2: AA batteries \(M\)\nHuge/6: Battery charger \(M\)|Huge/6: Battery charger \(M\)\nTest/2: AA batteries \(M\))\n$",
+    );
+}
+
+#[test]
+fn find_should_sort_by_descending_match_score() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "tiny", "Cable ties"]);
+    ctx.assert_pch(&["add", "tiny", "Cat toy"]);
+
+    ctx.assert_pch(&["find", "cat"])
+        .only_stdout_matches(r"^Tiny: Cat toy \(S\)\nTiny: Cable ties \(S\)\n$");
+}
+
+#[test]
+fn find_should_print_nothing_without_matches() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "tiny", "Screwdriver"]);
+
+    ctx.assert_pch(&["find", "zzz"]).is_silent();
+}
@@ -0,0 +1,24 @@
+#[macro_use]
+mod common;
+use common::*;
+
+#[test]
+fn history_should_show_every_action_oldest_first() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/2", "Test item"]);
+    ctx.assert_pch(&["delete", "Test item"]);
+    ctx.assert_pch(&["history"])
+        .only_stdout_matches(r"^(\d{4}-\d\d-\d\d \d\d:\d\d:\d\d UTC: .*\n){5}$");
+    ctx.assert_pch(&["history"]).only_stdout_matches(
+        r"UTC: add location Test\n.*UTC: add location Tiny\n.*UTC: add location Huge\n.*UTC: add item Test item\n.*UTC: delete items matching Test item\n$",
+    );
+}
+
+#[test]
+fn history_of_an_empty_store_should_be_silent() {
+    init!(ctx);
+
+    ctx.assert_pch(&["history"]).is_silent();
+}
@@ -0,0 +1,74 @@
+#[macro_use]
+mod common;
+use common::*;
+
+#[test]
+fn importing_should_add_items_and_create_locations() {
+    init!(ctx);
+    ctx.populate();
+
+    let path = ctx.temp_dir.path().join("items.csv");
+    std::fs::write(
+        &path,
+        "location,bin,name,size\nTest,3,\"Nuts, bolts\",M\ngarage,2,Rake,X\nGarage,,Hose,L\n",
+    )
+    .unwrap();
+
+    ctx.assert_pch(&["import", path.to_str().unwrap()])
+        .only_stdout_contains("Test/3: Nuts, bolts (M)\ngarage/2: Rake (X)\ngarage/1: Hose (L)\n");
+    ctx.assert_pch(&["locations"])
+        .only_stdout_contains("garage (2 bins)");
+    ctx.assert_pch(&["undo"])
+        .only_stdout_contains("Undid: import 3 items");
+    ctx.assert_pch(&["items"]).is_silent();
+}
+
+#[test]
+fn importing_should_round_trip_exported_items() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/3", "Nuts, bolts", "M"]);
+    ctx.assert_pch(&["add", "Huge/12", "Test item", "L"]);
+
+    let path = ctx.temp_dir.path().join("items.csv");
+    let exported = ctx.pch_assert_cmd(&["export"]).output().unwrap().stdout;
+    std::fs::write(&path, exported).unwrap();
+
+    ctx.assert_pch(&["import", path.to_str().unwrap()]);
+    ctx.assert_pch(&["items"]).only_stdout_contains(
+        "Huge/12: Test item (L)\nHuge/12: Test item (L)\nTest/3:  Nuts, bolts (M)\nTest/3:  Nuts, bolts (M)\n",
+    );
+}
+
+#[test]
+fn importing_should_round_trip_names_with_newlines() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Tiny", "Two\nlines"]);
+
+    let path = ctx.temp_dir.path().join("items.csv");
+    let exported = ctx.pch_assert_cmd(&["export"]).output().unwrap().stdout;
+    std::fs::write(&path, exported).unwrap();
+
+    ctx.assert_pch(&["delete", "Two*"]);
+    ctx.assert_pch(&["import", path.to_str().unwrap()]);
+    ctx.assert_pch(&["items"])
+        .only_stdout_contains("Tiny: Two\nlines (S)\n");
+}
+
+#[test]
+fn importing_an_invalid_size_should_add_nothing() {
+    init!(ctx);
+    ctx.populate();
+
+    let path = ctx.temp_dir.path().join("items.csv");
+    std::fs::write(&path, "Test,1,Good item,S\nNew place,1,Bad item,Q\n").unwrap();
+
+    ctx.assert_pch_fails(&["import", path.to_str().unwrap()])
+        .only_stderr_matches("line 2");
+    ctx.assert_pch(&["items"]).is_silent();
+    ctx.assert_pch(&["locations"])
+        .only_stdout_matches(r"^Test \(4 bins\)\nTiny\nHuge \(16 bins\)\n$");
+}
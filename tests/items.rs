@@ -26,7 +26,153 @@ fn items_should_sort_by_numeric_bin() {
     ctx.assert_pch(&["add", "huge/16", "Huge far item", "M"]);
 
     ctx.assert_pch(&["items", "item"]).only_stdout_matches(
-        r"Huge/6: Huge item \(M\)
+        r"Huge/6:  Huge item \(M\)
 Huge/16: Huge far item \(M\)",
     );
 }
+
+#[test]
+fn items_should_be_printable_as_json() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "test/4", "Test item", "M"]);
+    ctx.assert_pch(&["add", "tiny", "Batteries", "-n", "8"]);
+    ctx.assert_pch(&["add", "huge/6", "Huge item", "L"]);
+
+    ctx.assert_pch(&["items", "--json"])
+        .stderr(predicates::str::is_empty())
+        .stdout(is_json(
+            r#"
+[
+    {
+        "object_id": 6,
+        "location": "Huge",
+        "bin_no": 6,
+        "name": "Huge item",
+        "size": "L",
+        "count": 1
+    },
+    {
+        "object_id": 4,
+        "location": "Test",
+        "bin_no": 4,
+        "name": "Test item",
+        "size": "M",
+        "count": 1
+    },
+    {
+        "object_id": 5,
+        "location": "Tiny",
+        "bin_no": 1,
+        "name": "Batteries",
+        "size": "S",
+        "count": 8
+    }
+]
+            "#,
+        ));
+}
+
+#[test]
+fn items_should_be_sortable_by_name_size_and_bin() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "test/4", "apple", "M"]);
+    ctx.assert_pch(&["add", "huge/2", "Cherries", "S", "-n", "3"]);
+    ctx.assert_pch(&["add", "tiny", "Banana", "L"]);
+
+    ctx.assert_pch(&["items", "--sort", "name"])
+        .only_stdout_matches(
+            r"^Test/4: apple \(M\)
+Tiny:   Banana \(L\)
+Huge/2: Cherries \(S x3\)
+$",
+        );
+    ctx.assert_pch(&["items", "--sort", "size"])
+        .only_stdout_matches(
+            r"^Huge/2: Cherries \(S x3\)
+Tiny:   Banana \(L\)
+Test/4: apple \(M\)
+$",
+        );
+    ctx.assert_pch(&["items", "--sort", "bin", "--reverse"])
+        .only_stdout_matches(
+            r"^Test/4: apple \(M\)
+Huge/2: Cherries \(S x3\)
+Tiny:   Banana \(L\)
+$",
+        );
+}
+
+#[test]
+fn items_should_be_filterable_by_size() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "test/4", "apple", "M"]);
+    ctx.assert_pch(&["add", "huge/2", "Cherries", "S"]);
+    ctx.assert_pch(&["add", "huge/3", "Durian", "X"]);
+    ctx.assert_pch(&["add", "tiny", "Banana", "L"]);
+
+    ctx.assert_pch(&["items", "--size", "M+"])
+        .only_stdout_matches(
+            r"^Huge/3: Durian \(X\)
+Test/4: apple \(M\)
+Tiny:   Banana \(L\)
+$",
+        );
+    ctx.assert_pch(&["items", "--size", "S,X"])
+        .only_stdout_matches(
+            r"^Huge/2: Cherries \(S\)
+Huge/3: Durian \(X\)
+$",
+        );
+    ctx.assert_pch_fails(&["items", "--size", "Q+"])
+        .only_stderr_matches("invalid size filter");
+}
+
+#[test]
+fn items_should_be_printable_with_a_template() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "test/4", "apple", "M", "-n", "2"]);
+    ctx.assert_pch(&["add", "tiny", "Banana", "L"]);
+
+    ctx.assert_pch(&[
+        "items",
+        "--format",
+        "{location}/{bin} {name} [{size}] {{x{count}}}",
+    ])
+    .only_stdout_matches(
+        r"^Test/4 apple \[M\] \{x2\}
+Tiny/1 Banana \[L\] \{x1\}
+$",
+    );
+    ctx.assert_pch_fails(&["items", "--format", "{location} {colour}"])
+        .only_stderr_matches(r"unknown placeholder \{colour\}");
+}
+
+#[test]
+fn items_should_list_empty_bins() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/1", "Hammer"]);
+    ctx.assert_pch(&["add", "Test/3", "Saw"]);
+    ctx.assert_pch(&["add", "Tiny", "Nails"]);
+    for bin_no in 1..=16 {
+        if bin_no != 5 && bin_no != 9 {
+            ctx.assert_pch(&["add", &format!("Huge/{}", bin_no), "Anvil", "--force"]);
+        }
+    }
+
+    ctx.assert_pch(&["items", "--empty-bins"])
+        .only_stdout_contains("Huge: empty bins 5, 9\nTest: empty bins 2, 4\n");
+
+    ctx.assert_pch(&["delete", "Nails"]);
+    ctx.assert_pch(&["items", "--empty-bins"])
+        .only_stdout_contains("Huge: empty bins 5, 9\nTest: empty bins 2, 4\nTiny: empty\n");
+}
@@ -0,0 +1,44 @@
+#[macro_use]
+mod common;
+use common::*;
+
+#[test]
+fn labels_should_box_each_item() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/1", "Hammer"]);
+    ctx.assert_pch(&["add", "Tiny", "Nails", "-n", "20"]);
+
+    ctx.assert_pch(&["labels", "--width", "16"])
+        .only_stdout_contains(
+            "+--------------+
+| Test/1:      |
+| Hammer (S)   |
++--------------+
++--------------+
+| Tiny: Nails  |
+| (S x20)      |
++--------------+
+",
+        );
+}
+
+#[test]
+fn labels_should_break_words_too_long_for_a_line() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/1", "Supercalifragilistic"]);
+
+    ctx.assert_pch(&["labels", "super*", "--width", "12"])
+        .only_stdout_contains(
+            "+----------+
+| Test/1:  |
+| Supercal |
+| ifragili |
+| stic (S) |
++----------+
+",
+        );
+}
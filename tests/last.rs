@@ -0,0 +1,62 @@
+#[macro_use]
+mod common;
+use common::*;
+
+use qualia::{object, Object, Store, Q};
+
+#[test]
+fn last_should_show_the_newest_items_first() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/1", "Hammer"]);
+    ctx.assert_pch(&["add", "Huge/6", "Anvil", "X"]);
+    ctx.assert_pch(&["add", "Tiny", "Nails"]);
+
+    ctx.assert_pch(&["last"]).only_stdout_matches(
+        r"^Tiny:   Nails \(S\)
+Huge/6: Anvil \(X\)
+Test/1: Hammer \(S\)
+$",
+    );
+    ctx.assert_pch(&["last", "2"]).only_stdout_matches(
+        r"^Tiny:   Nails \(S\)
+Huge/6: Anvil \(X\)
+$",
+    );
+}
+
+#[test]
+fn last_should_show_items_without_a_timestamp_after_the_rest() {
+    init!(ctx);
+    ctx.populate();
+
+    let mut store = Store::open(ctx.store_path()).unwrap();
+    let location_id = store
+        .query(Q.equal("type", "location").equal("name", "Tiny"))
+        .iter()
+        .unwrap()
+        .next()
+        .unwrap()["object_id"]
+        .as_number()
+        .unwrap();
+    let checkpoint = store.checkpoint().unwrap();
+    checkpoint
+        .add(object!(
+            "type" => "item",
+            "name" => "Old box",
+            "location_id" => location_id,
+            "bin_no" => 1,
+            "size" => "M",
+        ))
+        .unwrap();
+    checkpoint.commit("add old item").unwrap();
+
+    ctx.assert_pch(&["add", "Test/1", "Hammer"]);
+
+    ctx.assert_pch(&["last"]).only_stdout_matches(
+        r"^Test/1: Hammer \(S\)
+Tiny:   Old box \(M\)
+$",
+    );
+}
@@ -27,6 +27,51 @@ fn an_added_location_with_one_bin_should_not_include_bins() {
         .only_stdout_matches("^Test\n");
 }
 
+#[test]
+fn locations_should_be_printable_as_json() {
+    init!(ctx);
+
+    ctx.assert_pch(&["add-location", "Shelf", "3"]);
+    ctx.assert_pch(&["add-location", "Box", "1"]);
+    ctx.assert_pch(&["add", "shelf/1", "Hammer", "M"]);
+    ctx.assert_pch(&["add", "shelf/3", "Batteries", "-n", "4"]);
+
+    ctx.assert_pch(&["locations", "--json"])
+        .stderr(predicates::str::is_empty())
+        .stdout(is_json(
+            r#"
+[
+    {
+        "object_id": 2,
+        "name": "Box",
+        "num_bins": 1,
+        "bins": [0]
+    },
+    {
+        "object_id": 1,
+        "name": "Shelf",
+        "num_bins": 3,
+        "bins": [3, 0, 8]
+    }
+]
+"#,
+        ));
+}
+
+#[test]
+fn locations_should_show_which_bins_are_occupied() {
+    init!(ctx);
+
+    ctx.assert_pch(&["add-location", "Shelf", "6"]);
+    ctx.assert_pch(&["add-location", "Box", "1"]);
+    ctx.assert_pch(&["add", "shelf/1", "Hammer"]);
+    ctx.assert_pch(&["add", "shelf/5", "Saw"]);
+    ctx.assert_pch(&["add", "shelf/5", "Drill"]);
+
+    ctx.assert_pch(&["locations", "--occupancy"])
+        .only_stdout_matches(r"^Shelf \(6 bins\) \[X\.\.\.X\.\]\nBox\n$");
+}
+
 #[test]
 fn creating_a_location_with_an_invalid_number_of_bins_should_fail() {
     init!(ctx);
@@ -35,3 +80,189 @@ fn creating_a_location_with_an_invalid_number_of_bins_should_fail() {
     ctx.assert_pch_fails(&["add-location", "Zero", "0"]);
     ctx.assert_pch_fails(&["add-location", "Negative", "-1"]);
 }
+
+#[test]
+fn an_added_location_should_show_its_alias() {
+    init!(ctx);
+
+    ctx.assert_pch(&["add-location", "Kitchen", "4", "--alias", "k"])
+        .is_silent();
+    ctx.assert_pch(&["locations"])
+        .only_stdout_contains("Kitchen [k] (4 bins)");
+}
+
+#[test]
+fn child_locations_should_be_shown_under_their_parents() {
+    init!(ctx);
+
+    ctx.assert_pch(&["add-location", "Garage", "1"]);
+    ctx.assert_pch(&["add-location", "Attic", "2"]);
+    ctx.assert_pch(&["add-location", "Left shelf", "4", "--parent", "Garage"])
+        .is_silent();
+    ctx.assert_pch(&["add-location", "Top drawer", "1", "--parent", "Left shelf"]);
+    ctx.assert_pch(&["add-location", "Right shelf", "3", "--parent", "garage"]);
+
+    ctx.assert_pch(&["locations"]).only_stdout_matches(
+        r"^Garage
+    Left shelf \(4 bins\)
+        Top drawer
+    Right shelf \(3 bins\)
+Attic \(2 bins\)
+$",
+    );
+}
+
+#[test]
+fn creating_a_location_with_a_missing_parent_should_fail() {
+    init!(ctx);
+
+    ctx.assert_pch_fails(&["add-location", "Left shelf", "4", "--parent", "Garage"])
+        .only_stderr_matches("did not match exactly one location");
+}
+
+#[test]
+fn a_misspelled_location_should_suggest_a_close_one() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch_fails(&["add", "Hge", "Test item"])
+        .only_stderr_matches(r#"did not match exactly one location; did you mean "Huge"\?"#);
+    ctx.assert_pch_fails(&["add", "Qwerty", "Test item"])
+        .only_stderr_matches(r#"did not match exactly one location\n$"#);
+}
+
+#[test]
+fn an_ambiguous_location_should_list_its_matches_without_a_terminal() {
+    init!(ctx);
+
+    ctx.assert_pch(&["add-location", "Top shelf", "1"]);
+    ctx.assert_pch(&["add-location", "Bottom shelf", "1"]);
+
+    ctx.assert_pch_fails(&["add", "shelf", "Test item"])
+        .only_stderr_matches("it matched Bottom shelf, Top shelf");
+}
+
+#[test]
+fn creating_a_location_with_a_duplicate_alias_should_fail() {
+    init!(ctx);
+
+    ctx.assert_pch(&["add-location", "Kitchen", "4", "--alias", "k"]);
+    ctx.assert_pch_fails(&["add-location", "Kennel", "1", "--alias", "K"])
+        .only_stderr_matches("Error: .*\"K\".*Kitchen");
+    ctx.assert_pch(&["locations"])
+        .only_stdout_matches(r"^Kitchen \[k\] \(4 bins\)\n$");
+}
+
+#[test]
+fn creating_a_location_with_an_invalid_alias_should_fail() {
+    init!(ctx);
+
+    ctx.assert_pch_fails(&["add-location", "Kitchen", "4", "--alias", "k/2"]);
+    ctx.assert_pch_fails(&["add-location", "Kitchen", "4", "--alias", "k 2"]);
+}
+
+#[test]
+fn renaming_a_location_should_update_its_items() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/2", "Test item"]);
+    ctx.assert_pch(&["rename-location", "test", "Pantry"])
+        .is_silent();
+    ctx.assert_pch(&["items"])
+        .only_stdout_contains("Pantry/2: Test item");
+    ctx.assert_pch(&["undo"])
+        .only_stdout_contains("Undid: rename location Test to Pantry");
+    ctx.assert_pch(&["items"])
+        .only_stdout_contains("Test/2: Test item");
+}
+
+#[test]
+fn renaming_a_location_to_an_existing_name_should_fail() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch_fails(&["rename-location", "test", "huge"])
+        .only_stderr_matches("Error: .*Huge.*exists");
+}
+
+#[test]
+fn merging_locations_should_move_items_and_remove_the_source() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add-location", "test", "2"]);
+    ctx.assert_pch(&["add", "Tiny", "Tiny item", "M"]);
+    ctx.assert_pch(&["add", "Test", "Test item"]);
+    ctx.assert_pch(&["merge-locations", "Tiny", "Test"])
+        .is_silent();
+    ctx.assert_pch(&["locations"])
+        .only_stdout_matches(r"^Test \(4 bins\)\nHuge \(16 bins\)\ntest \(2 bins\)\n$");
+    ctx.assert_pch(&["items"])
+        .only_stdout_contains("Test/1: Test item (S)\nTest/1: Tiny item (M)\n");
+}
+
+#[test]
+fn merging_locations_should_rebin_items_that_do_not_fit() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/1", "Small item", "S"]);
+    ctx.assert_pch(&["add", "Huge/1", "Kept item", "L"]);
+    ctx.assert_pch(&["add", "Huge/9", "Moved item", "M"]);
+    ctx.assert_pch(&["merge-locations", "Huge", "Test"])
+        .is_silent();
+    ctx.assert_pch(&["items"]).only_stdout_contains(
+        "Test/1: Kept item (L)\nTest/1: Small item (S)\nTest/2: Moved item (M)\n",
+    );
+    ctx.assert_pch(&["undo"])
+        .only_stdout_contains("Undid: merge location Huge into Test");
+    ctx.assert_pch(&["items"]).only_stdout_contains(
+        "Huge/1: Kept item (L)\nHuge/9: Moved item (M)\nTest/1: Small item (S)\n",
+    );
+}
+
+#[test]
+fn merging_a_location_into_itself_should_fail() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch_fails(&["merge-locations", "Test", "test"])
+        .only_stderr_matches("into itself");
+}
+
+#[test]
+fn merging_locations_should_move_child_locations_to_the_destination() {
+    init!(ctx);
+
+    ctx.assert_pch(&["add-location", "Garage", "1"]);
+    ctx.assert_pch(&["add-location", "Attic", "1"]);
+    ctx.assert_pch(&["add-location", "Left shelf", "4", "--parent", "Garage"]);
+    ctx.assert_pch(&["merge-locations", "Garage", "Attic"])
+        .is_silent();
+
+    ctx.assert_pch(&["locations"]).only_stdout_matches(
+        r"^Attic
+    Left shelf \(4 bins\)
+$",
+    );
+}
+
+#[test]
+fn merging_a_location_into_its_child_should_keep_the_tree_acyclic() {
+    init!(ctx);
+
+    ctx.assert_pch(&["add-location", "House", "1"]);
+    ctx.assert_pch(&["add-location", "Garage", "1", "--parent", "House"]);
+    ctx.assert_pch(&["add-location", "Left shelf", "4", "--parent", "Garage"]);
+    ctx.assert_pch(&["add-location", "Top drawer", "1", "--parent", "Left shelf"]);
+    ctx.assert_pch(&["merge-locations", "Garage", "Top drawer"])
+        .is_silent();
+
+    ctx.assert_pch(&["locations"]).only_stdout_matches(
+        r"^House
+    Left shelf \(4 bins\)
+        Top drawer
+$",
+    );
+}
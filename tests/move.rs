@@ -0,0 +1,70 @@
+#[macro_use]
+mod common;
+use common::*;
+
+#[test]
+fn items_should_be_movable() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/4", "Test item"])
+        .only_stdout_contains("Test/4: Test item");
+    ctx.assert_pch(&["move", "Test item", "Huge/3"])
+        .only_stdout_matches(r"^Moved Huge/3: Test item \(S\)\n$");
+    ctx.assert_pch(&["items"])
+        .only_stdout_matches(r"^Huge/3: Test item \(S\)\n$");
+}
+
+#[test]
+fn moving_should_choose_a_bin_when_none_is_given() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/1", "Anvil", "X"])
+        .only_stdout_contains("Test/1: Anvil");
+    ctx.assert_pch(&["add", "Huge/9", "Widget"])
+        .only_stdout_contains("Huge/9: Widget");
+    ctx.assert_pch(&["move", "Widget", "Test"])
+        .only_stdout_contains("Moved Test/2: Widget");
+}
+
+#[test]
+fn moving_should_be_undoable() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/4", "Test item"])
+        .only_stdout_contains("Test/4: Test item");
+    ctx.assert_pch(&["move", "Test item", "Tiny"])
+        .only_stdout_contains("Moved Tiny: Test item");
+    ctx.assert_pch(&["undo"])
+        .only_stdout_contains("Undid: move items matching Test item to Tiny");
+    ctx.assert_pch(&["items"])
+        .only_stdout_contains("Test/4: Test item");
+}
+
+#[test]
+fn moving_multiple_items_without_confirmation_should_fail() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/4", "Test item"])
+        .only_stdout_contains("Test/4: Test item");
+    ctx.assert_pch(&["add", "Test/1", "Also test item"])
+        .only_stdout_contains("Test/1: Also test item");
+    ctx.assert_pch_fails(&["move", "Test", "Tiny"])
+        .only_stderr_matches(r"Also test item.*\n.*Test item");
+    ctx.assert_pch(&["move", "--all", "Test", "Tiny"])
+        .only_stdout_matches(r"^Moved Tiny: Also test item \(S\)\nMoved Tiny: Test item \(S\)\n$");
+}
+
+#[test]
+fn moving_to_a_missing_bin_should_fail() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/4", "Test item"])
+        .only_stdout_contains("Test/4: Test item");
+    ctx.assert_pch_fails(&["move", "Test item", "Test/5"])
+        .only_stderr_matches("location Test only has 4 bins");
+}
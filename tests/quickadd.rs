@@ -41,3 +41,177 @@ fn quick_addition_into_specified_bin() -> rexpect::errors::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn quick_addition_with_an_inline_bin() -> rexpect::errors::Result<()> {
+    init!(ctx);
+    ctx.populate();
+
+    let mut p = spawn_command(ctx.pch_cmd(&["quickadd", "Test/4"]), Some(1000))?;
+    p.exp_string("Test/4> ")?;
+    p.send_line("2 Test 1 M")?;
+    p.exp_string("Test/2: Test 1 (M)")?;
+
+    p.exp_string("Test/4> ")?;
+    p.send_line("9 Test 2")?;
+    p.exp_string("Error: location Test only has 4 bins")?;
+
+    p.exp_string("Test/4> ")?;
+    p.send_line("Test 3")?;
+    p.exp_string("Test/4: Test 3 (S)")?;
+
+    p.process.exit()?;
+
+    Ok(())
+}
+
+#[test]
+fn quick_addition_counts_and_undoes_additions() -> rexpect::errors::Result<()> {
+    init!(ctx);
+    ctx.populate();
+
+    let mut p = spawn_command(ctx.pch_cmd(&["quickadd", "Test/1"]), Some(1000))?;
+    p.exp_string("Test/1> ")?;
+    p.send_line("undo")?;
+    p.exp_string("Nothing to undo")?;
+
+    p.exp_string("Test/1> ")?;
+    p.send_line("Test 1")?;
+    p.exp_string("(1 added)")?;
+
+    p.exp_string("Test/1> ")?;
+    p.send_line("Tset 2")?;
+    p.exp_string("(2 added)")?;
+
+    p.exp_string("Test/1> ")?;
+    p.send_line("\\u")?;
+    p.exp_string("Undid: add item Tset 2 (1 added)")?;
+
+    p.exp_string("Test/1> ")?;
+    p.send_line("Test 2")?;
+    p.exp_string("(2 added)")?;
+
+    p.exp_string("Test/1> ")?;
+    p.process.exit()?;
+
+    ctx.assert_pch(&["items"])
+        .only_stdout_contains("Test/1: Test 1 (S)\nTest/1: Test 2 (S)\n");
+
+    Ok(())
+}
+
+#[test]
+fn quick_addition_with_dry_run_should_not_add_items() -> rexpect::errors::Result<()> {
+    init!(ctx);
+    ctx.populate();
+
+    let mut p = spawn_command(
+        ctx.pch_cmd(&["quickadd", "Test/2", "--dry-run"]),
+        Some(1000),
+    )?;
+    p.exp_string("Test/2> ")?;
+    p.send_line("Test 1 L")?;
+    p.exp_string("(dry run) Test/2: Test 1 (L)")?;
+
+    p.process.exit()?;
+
+    ctx.assert_pch(&["items"]).is_silent();
+
+    Ok(())
+}
+
+#[test]
+fn quick_addition_from_piped_stdin() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.pch_assert_cmd(&["quickadd", "Test/3"])
+        .write_stdin("Test 1\n\nTest 2 L\n")
+        .assert()
+        .success()
+        .only_stdout_contains("Test/3: Test 1 (S)\nTest/3: Test 2 (L)\n");
+    ctx.assert_pch(&["items"])
+        .only_stdout_contains("Test/3: Test 1 (S)\nTest/3: Test 2 (L)\n");
+}
+
+#[test]
+fn quick_addition_from_file() {
+    init!(ctx);
+    ctx.populate();
+
+    let path = ctx.temp_dir.path().join("unpacked.txt");
+    std::fs::write(&path, "Test 1 M\nTest 2\n").unwrap();
+
+    ctx.assert_pch(&["quickadd", "Tiny", "--from-file", path.to_str().unwrap()])
+        .only_stdout_contains("Tiny: Test 1 (M)\nTiny: Test 2 (S)\n");
+}
+
+#[test]
+fn quick_addition_with_a_default_size() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.pch_assert_cmd(&["quickadd", "Test/3", "--default-size", "L"])
+        .write_stdin("Test 1\nTest 2 S\nM Test 3\n")
+        .assert()
+        .success()
+        .only_stdout_contains("Test/3: Test 1 (L)\nTest/3: Test 2 (S)\nTest/3: Test 3 (M)\n");
+}
+
+#[test]
+fn quick_addition_with_a_lowercase_default_size() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.pch_assert_cmd(&["quickadd", "Test/3", "--default-size", "l"])
+        .write_stdin("Test 1\n")
+        .assert()
+        .success()
+        .only_stdout_contains("Test/3: Test 1 (L)\n");
+}
+
+#[test]
+fn quick_addition_with_a_custom_prompt() -> rexpect::errors::Result<()> {
+    init!(ctx);
+    ctx.populate();
+
+    let mut p = spawn_command(
+        ctx.pch_cmd(&["quickadd", "Test/4", "--prompt", "unpacking> "]),
+        Some(1000),
+    )?;
+    p.exp_string("unpacking> ")?;
+    p.send_line("Test 1")?;
+    p.exp_regex(r"Test/4: Test 1 \(S\)")?;
+
+    p.process.exit()?;
+
+    Ok(())
+}
+
+#[test]
+fn quick_addition_with_a_size_prefix() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.pch_assert_cmd(&["quickadd", "Test/3"])
+        .write_stdin("M Test 1\nM\nX Test 2 L\n2 X Test 3\n")
+        .assert()
+        .success()
+        .only_stdout_contains(
+            "Test/3: Test 1 (M)\nTest/3: M (S)\nTest/3: X Test 2 (L)\nTest/2: Test 3 (X)\n",
+        );
+}
+
+#[test]
+fn quick_addition_into_a_bin_range() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.pch_assert_cmd(&["quickadd", "Huge/5-6"])
+        .write_stdin("Test 1\nTest 2\n1 Test 3\nTest 4 L\n")
+        .assert()
+        .success()
+        .only_stdout_contains(
+            "Huge/5: Test 1 (S)\nHuge/6: Test 2 (S)\nHuge/1: Test 3 (S)\nHuge/5: Test 4 (L)\n",
+        );
+}
@@ -0,0 +1,46 @@
+#[macro_use]
+mod common;
+use common::*;
+
+#[test]
+fn rebalancing_should_spread_items_across_bins() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/1", "Small", "S"]);
+    ctx.assert_pch(&["add", "Test/1", "Medium", "M"]);
+    ctx.assert_pch(&["add", "Test/1", "Large", "L"]);
+    ctx.assert_pch(&["add", "Test/1", "Huge", "X"]);
+
+    ctx.assert_pch(&["rebalance", "Test"])
+        .only_stdout_matches(r"^Moved 3 of 4 items\n$");
+    ctx.assert_pch(&["items"]).only_stdout_matches(
+        r"^Test/1: Huge \(X\)
+Test/2: Large \(L\)
+Test/3: Medium \(M\)
+Test/4: Small \(S\)
+$",
+    );
+
+    ctx.assert_pch(&["rebalance", "Test"])
+        .only_stdout_matches(r"^Moved 0 of 4 items\n$");
+}
+
+#[test]
+fn rebalancing_should_be_undoable() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/3", "Small", "S"]);
+    ctx.assert_pch(&["add", "Test/3", "Medium", "M"]);
+
+    ctx.assert_pch(&["rebalance", "Test"])
+        .only_stdout_matches(r"^Moved 1 of 2 items\n$");
+    ctx.assert_pch(&["undo"])
+        .only_stdout_contains("Undid: rebalance location Test");
+    ctx.assert_pch(&["items"]).only_stdout_matches(
+        r"^Test/3: Medium \(M\)
+Test/3: Small \(S\)
+$",
+    );
+}
@@ -0,0 +1,70 @@
+#[macro_use]
+mod common;
+use common::*;
+
+#[test]
+fn redoing_with_nothing_undone_should_do_nothing() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["redo"])
+        .only_stdout_contains("Nothing to redo");
+}
+
+#[test]
+fn undone_actions_should_be_redoable_in_order() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/2", "Test item"]);
+    ctx.assert_pch(&["rename-location", "Test", "Pantry"]);
+    ctx.assert_pch(&["undo"])
+        .only_stdout_contains("Undid: rename location Test to Pantry");
+    ctx.assert_pch(&["undo"])
+        .only_stdout_contains("Undid: add item Test item");
+    ctx.assert_pch(&["items"]).is_silent();
+
+    ctx.assert_pch(&["redo"])
+        .only_stdout_contains("Redid: add item Test item");
+    ctx.assert_pch(&["r"])
+        .only_stdout_contains("Redid: rename location Test to Pantry");
+    ctx.assert_pch(&["items"])
+        .only_stdout_contains("Pantry/2: Test item (S)\n");
+    ctx.assert_pch(&["redo"])
+        .only_stdout_contains("Nothing to redo");
+
+    ctx.assert_pch(&["undo"])
+        .only_stdout_contains("Undid: rename location Test to Pantry");
+    ctx.assert_pch(&["items"])
+        .only_stdout_contains("Test/2: Test item (S)\n");
+}
+
+#[test]
+fn redoing_should_restore_references_to_readded_locations() {
+    init!(ctx);
+
+    ctx.assert_pch(&["add-location", "Garage", "2"]);
+    ctx.assert_pch(&["add", "Garage/2", "Rake"]);
+    ctx.assert_pch(&["undo"]);
+    ctx.assert_pch(&["undo"]);
+    ctx.assert_pch(&["locations"]).is_silent();
+
+    ctx.assert_pch(&["redo"]);
+    ctx.assert_pch(&["redo"]);
+    ctx.assert_pch(&["items"])
+        .only_stdout_contains("Garage/2: Rake (S)\n");
+}
+
+#[test]
+fn redoing_after_another_change_should_do_nothing() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/2", "Test item"]);
+    ctx.assert_pch(&["undo"]);
+    ctx.assert_pch(&["add", "Test/3", "Other item"]);
+    ctx.assert_pch(&["redo"])
+        .only_stdout_contains("Nothing to redo");
+    ctx.assert_pch(&["items"])
+        .only_stdout_contains("Test/3: Other item (S)\n");
+}
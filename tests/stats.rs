@@ -0,0 +1,56 @@
+#[macro_use]
+mod common;
+use common::*;
+
+#[test]
+fn stats_should_summarize_each_location() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "test/1", "Small", "S"]);
+    ctx.assert_pch(&["add", "test/1", "Medium", "M"]);
+    ctx.assert_pch(&["add", "test/3", "Batteries", "S", "-n", "4"]);
+    ctx.assert_pch(&["add", "tiny", "Large", "L"]);
+
+    ctx.assert_pch(&["stats"]).only_stdout_matches(
+        r"^Huge: 0 items, fullness 0
+(    Huge/\d+: 0
+){16}Test: 3 items, fullness 13
+    Test/1: 5
+    Test/2: 0
+    Test/3: 8
+    Test/4: 0
+Tiny: 1 item, fullness 4
+$",
+    );
+}
+
+#[test]
+fn stats_should_list_nearly_full_bins() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add-location", "Shelf", "2", "--capacity", "10"]);
+    ctx.assert_pch(&["add", "test/1", "Huge", "X"]);
+    ctx.assert_pch(&["add", "test/2", "Large", "L"]);
+    ctx.assert_pch(&["add", "test/3", "Small", "S"]);
+    ctx.assert_pch(&["add", "shelf/1", "Batteries", "S", "-n", "4"]);
+    ctx.assert_pch(&["add", "shelf/2", "Medium", "M"]);
+    ctx.assert_pch(&["add", "tiny", "Large", "L"]);
+
+    ctx.assert_pch(&["stats", "--near-full"])
+        .only_stdout_matches(
+            r"^Shelf/1: 8 \(80% of capacity 10\)
+Test/1: 6 \(100% of the fullest bin\)
+$",
+        );
+    ctx.assert_pch(&["stats", "--near-full", "--threshold", "60"])
+        .only_stdout_matches(
+            r"^Shelf/1: 8 \(80% of capacity 10\)
+Test/1: 6 \(100% of the fullest bin\)
+Test/2: 4 \(66% of the fullest bin\)
+$",
+        );
+    ctx.assert_pch_fails(&["stats", "--near-full", "--threshold", "150%"])
+        .only_stderr_matches("must be a percentage");
+}
@@ -28,3 +28,111 @@ fn default_store_path_correct() {
         .join("pachinko.qualia")
         .exists());
 }
+
+fn assert_pch_with_auto_store(
+    ctx: &TestContext,
+    dir: &Path,
+    args: &[&str],
+) -> assert_cmd::assert::Assert {
+    let mut cmd = ctx.pch_cmd(args);
+    cmd.env_remove("PACHINKO_STORE_PATH")
+        .env("PACHINKO_STORE", "auto")
+        .current_dir(dir);
+
+    assert_cmd::Command::from(cmd).assert().success()
+}
+
+#[test]
+fn auto_store_path_finds_a_store_in_a_parent_directory() {
+    init!(ctx);
+
+    let project_dir = ctx.temp_dir.path().join("project");
+    let nested_dir = project_dir.join("a").join("b");
+    std::fs::create_dir_all(&nested_dir).unwrap();
+
+    let local_store_path = project_dir.join(".pachinko.qualia");
+    ctx.assert_pch(&[
+        "add-location",
+        "--store-path",
+        local_store_path.to_str().unwrap(),
+        "Local",
+        "2",
+    ]);
+
+    assert_pch_with_auto_store(&ctx, &nested_dir, &["locations"])
+        .only_stdout_contains("Local (2 bins)");
+}
+
+#[test]
+fn custom_store_paths_should_have_their_directories_created() {
+    init!(ctx);
+
+    let store_path = ctx
+        .temp_dir
+        .path()
+        .join("new")
+        .join("dir")
+        .join("inv.qualia");
+    ctx.assert_pch(&[
+        "add-location",
+        "--store-path",
+        store_path.to_str().unwrap(),
+        "Test",
+        "4",
+    ]);
+
+    assert!(store_path.exists());
+}
+
+#[test]
+fn custom_store_paths_should_fail_clearly_when_their_directories_cannot_be_created() {
+    init!(ctx);
+
+    let blocker = ctx.temp_dir.path().join("file");
+    std::fs::write(&blocker, "").unwrap();
+
+    ctx.assert_pch_fails(&[
+        "locations",
+        "--store-path",
+        blocker.join("inv.qualia").to_str().unwrap(),
+    ])
+    .only_stderr_matches("failed to create directory .*file for store");
+}
+
+#[test]
+fn auto_store_path_falls_back_to_the_default_store() {
+    init!(ctx);
+
+    assert_pch_in_home(&ctx, &["add-location", "Test", "16"]);
+
+    assert_pch_with_auto_store(&ctx, ctx.temp_dir.path(), &["locations"])
+        .only_stdout_contains("Test (16 bins)");
+}
+
+#[test]
+fn read_only_stores_should_not_change() {
+    init!(ctx);
+    ctx.populate();
+    ctx.assert_pch(&["add", "Test/1", "Hammer"]);
+
+    ctx.assert_pch(&["items", "--read-only"])
+        .only_stdout_contains("Test/1: Hammer (S)\n");
+    ctx.assert_pch_fails(&["add", "--read-only", "Test/2", "Saw"])
+        .only_stderr_matches("store opened read-only");
+    ctx.assert_pch_fails(&["delete", "--read-only", "Hammer"])
+        .only_stderr_matches("store opened read-only");
+    ctx.assert_pch_fails(&["undo", "--read-only"])
+        .only_stderr_matches("store opened read-only");
+
+    ctx.assert_pch(&["items"])
+        .only_stdout_contains("Test/1: Hammer (S)\n");
+}
+
+#[test]
+fn read_only_stores_should_not_be_created() {
+    init!(ctx);
+
+    ctx.assert_pch_fails(&["items", "--read-only"])
+        .only_stderr_matches("store .* does not exist");
+    assert!(!Path::new(&ctx.store_path()).exists());
+}
@@ -0,0 +1,44 @@
+#[macro_use]
+mod common;
+use common::*;
+
+#[test]
+fn swapping_bins_should_exchange_their_items() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/2", "Hammer"]);
+    ctx.assert_pch(&["add", "Test/2", "Saw"]);
+    ctx.assert_pch(&["add", "Test/4", "Nails"]);
+    ctx.assert_pch(&["add", "Test/3", "Glue"]);
+
+    ctx.assert_pch(&["swap-bins", "Test", "2", "4"])
+        .only_stdout_matches(
+            r"^Moved 2 items from Test/2 to Test/4
+Moved 1 item from Test/4 to Test/2
+$",
+        );
+    ctx.assert_pch(&["items"]).only_stdout_matches(
+        r"^Test/2: Nails \(S\)
+Test/3: Glue \(S\)
+Test/4: Hammer \(S\)
+Test/4: Saw \(S\)
+$",
+    );
+
+    ctx.assert_pch(&["undo"])
+        .only_stdout_contains("Undid: swap bins 2 and 4 of Test");
+    ctx.assert_pch(&["items", "Nails"])
+        .only_stdout_contains("Test/4: Nails");
+}
+
+#[test]
+fn swapping_bins_should_fail_for_bins_outside_the_location() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch_fails(&["swap-bins", "Test", "2", "5"])
+        .only_stderr_matches("bin 5 is outside location Test, which only has 4 bins");
+    ctx.assert_pch_fails(&["swap-bins", "Test", "2", "2"])
+        .only_stderr_matches("can't swap bin 2 with itself");
+}
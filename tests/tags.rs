@@ -0,0 +1,80 @@
+#[macro_use]
+mod common;
+use common::*;
+
+#[test]
+fn items_should_be_filterable_by_tag() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&[
+        "add", "Test/1", "Vase", "--tag", "fragile", "--tag", "decor",
+    ]);
+    ctx.assert_pch(&["add", "Huge/2", "Wreath", "--tag", "seasonal"]);
+    ctx.assert_pch(&["add", "Tiny", "Hammer"]);
+
+    ctx.assert_pch(&["items", "--tag", "Fragile"])
+        .only_stdout_matches(r"^Test/1: Vase \(S\)\n$");
+    ctx.assert_pch(&["items", "--tag", "nothing"]).is_silent();
+}
+
+#[test]
+fn items_should_be_taggable_and_untaggable() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/1", "Vase", "--tag", "fragile"]);
+    ctx.assert_pch(&["add", "Huge/2", "Wreath"]);
+
+    ctx.assert_pch(&["tag", "Wreath", "seasonal", "fragile"])
+        .only_stdout_matches(r"^Huge/2: Wreath \(S\) \[seasonal, fragile\]\n$");
+    ctx.assert_pch(&["items", "--tag", "fragile"])
+        .only_stdout_matches(r"^Huge/2: Wreath \(S\)\nTest/1: Vase \(S\)\n$");
+
+    ctx.assert_pch(&["untag", "Wreath", "fragile"])
+        .only_stdout_matches(r"^Huge/2: Wreath \(S\) \[seasonal\]\n$");
+    ctx.assert_pch(&["items", "--tag", "fragile"])
+        .only_stdout_matches(r"^Test/1: Vase \(S\)\n$");
+
+    ctx.assert_pch(&["undo"])
+        .only_stdout_contains("Undid: untag items matching Wreath with fragile");
+    ctx.assert_pch(&["items", "--tag", "fragile"])
+        .only_stdout_contains("Huge/2: Wreath");
+}
+
+#[test]
+fn tagging_multiple_items_without_confirmation_should_fail() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/1", "Red hat"]);
+    ctx.assert_pch(&["add", "Test/2", "Blue hat"]);
+
+    ctx.assert_pch_fails(&["tag", "hat", "clothes"])
+        .only_stderr_matches("use --all to tag multiple items");
+    ctx.assert_pch(&["tag", "--all", "hat", "clothes"]);
+    ctx.assert_pch(&["items", "--tag", "clothes"])
+        .only_stdout_matches(r"^Test/1: Red hat \(S\)\nTest/2: Blue hat \(S\)\n$");
+}
+
+#[test]
+fn tags_should_be_single_words() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch_fails(&["add", "Test/1", "Vase", "--tag", "a,b"])
+        .only_stderr_matches("must be a single word without commas");
+}
+
+#[test]
+fn tags_should_survive_a_dump() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&[
+        "add", "Test/1", "Vase", "--tag", "fragile", "--tag", "decor",
+    ]);
+
+    ctx.assert_pch(&["dump"])
+        .only_stdout_contains(r#""tags":"fragile,decor""#);
+}
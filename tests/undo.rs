@@ -0,0 +1,18 @@
+#[macro_use]
+mod common;
+use common::*;
+
+#[test]
+fn undo_list_should_show_recent_actions_without_undoing() {
+    init!(ctx);
+    ctx.populate();
+
+    ctx.assert_pch(&["add", "Test/2", "Test item"]);
+    ctx.assert_pch(&["undo", "--list", "2"])
+        .only_stdout_contains("add item Test item\nadd location Huge\n");
+    ctx.assert_pch(&["undo", "--list"]).only_stdout_contains(
+        "add item Test item\nadd location Huge\nadd location Tiny\nadd location Test\n",
+    );
+    ctx.assert_pch(&["items"])
+        .only_stdout_contains("Test/2: Test item (S)\n");
+}